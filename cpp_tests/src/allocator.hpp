@@ -0,0 +1,75 @@
+#pragma once
+
+#include <cstddef>
+#include <new>
+#include <utility>
+#include "api.hpp"
+
+// Allocates through the `RawAllocator` handed over by the runner, so that the
+// C++ solutions get the same allocation statistics as the Rust ones.
+class Alloc {
+    const RawAllocator* raw;
+
+  public:
+    explicit Alloc(const RawAllocator* raw) : raw(raw) {
+    }
+
+    void* allocate(size_t size, size_t align) const {
+        void* ptr = raw->alloc(raw->ctx, size, align);
+        if (!ptr) {
+            throw std::bad_alloc();
+        }
+        return ptr;
+    }
+
+    void deallocate(void* ptr, size_t size, size_t align) const {
+        raw->dealloc(raw->ctx, static_cast<uint8_t*>(ptr), size, align);
+    }
+
+    template <typename T, typename... Args>
+    T* create(Args&&... args) const {
+        return new (allocate(sizeof(T), alignof(T))) T{ std::forward<Args>(args)... };
+    }
+
+    template <typename T>
+    void destroy(T* ptr) const {
+        ptr->~T();
+        deallocate(ptr, sizeof(T), alignof(T));
+    }
+
+    bool operator==(const Alloc& other) const {
+        return raw == other.raw;
+    }
+};
+
+// `Alloc` as a standard library allocator.
+template <typename T>
+class StlAlloc {
+  public:
+    using value_type = T;
+
+    Alloc alloc;
+
+    explicit StlAlloc(Alloc alloc) : alloc(alloc) {
+    }
+    template <typename U>
+    StlAlloc(const StlAlloc<U>& other) : alloc(other.alloc) {
+    }
+
+    T* allocate(size_t n) {
+        return static_cast<T*>(alloc.allocate(n * sizeof(T), alignof(T)));
+    }
+
+    void deallocate(T* ptr, size_t n) {
+        alloc.deallocate(ptr, n * sizeof(T), alignof(T));
+    }
+
+    template <typename U>
+    bool operator==(const StlAlloc<U>& other) const {
+        return alloc == other.alloc;
+    }
+    template <typename U>
+    bool operator!=(const StlAlloc<U>& other) const {
+        return !(alloc == other.alloc);
+    }
+};
@@ -0,0 +1,48 @@
+#pragma once
+
+#include <optional>
+
+// C++ counterpart of the `DoubleLinkedList` trait from
+// rust_tests/src/solutions/double_linked_list.rs.
+//
+// A solution is a class template `L<T>` deriving from
+// `DoubleLinkedList<L<T>, T, NodeRef>` that provides:
+//
+//     using NodeRef = ...; // copyable and comparable
+//     L(Alloc alloc, size_t capacity);
+//
+//     NodeRef insert_after(NodeRef node, T value);
+//     NodeRef insert_before(NodeRef node, T value);
+//     NodeRef push_back(T value);
+//     NodeRef push_front(T value);
+//
+//     void remove(NodeRef node); // `delete` in Rust
+//
+//     std::optional<NodeRef> next(NodeRef node) const;
+//     std::optional<NodeRef> prec(NodeRef node) const;
+//     std::optional<NodeRef> first() const;
+//     std::optional<NodeRef> last() const;
+//
+//     const T* value(NodeRef node) const;
+//     T* value_mut(NodeRef node);
+//
+// Nodes are allocated through `alloc`. `search` has a default implementation,
+// like in the trait.
+template <typename Self, typename T, typename NodeRef>
+class DoubleLinkedList {
+  public:
+    template <typename F>
+    std::optional<NodeRef> search(F f) const {
+        auto& self = static_cast<const Self&>(*this);
+        for (auto it = self.first(); it; it = self.next(*it)) {
+            const T* value = self.value(*it);
+            if (!value) {
+                return std::nullopt;
+            }
+            if (f(*value)) {
+                return it;
+            }
+        }
+        return std::nullopt;
+    }
+};
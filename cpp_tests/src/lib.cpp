@@ -1,5 +1,8 @@
 #include <cstring>
+#include <type_traits>
+#include <vector>
 #include "api.hpp"
+#include "scenarios.hpp"
 #include "solutions/solutions.hpp"
 
 #ifdef _WIN32
@@ -9,35 +12,101 @@
 #endif
 
 extern "C" {
-EXPORT RawLoadTestsResult load_tests();
+EXPORT uint32_t api_version();
+EXPORT RawLoadResult load_tests();
 }
 
-template <typename T>
-RawTestData sol(const char* name) {
-    auto create = [](uintptr_t nodes) noexcept -> Handle {
-        auto ptr = new T(nodes);
-        return ptr;
+static const uint8_t* str(const char* s) {
+    return reinterpret_cast<const uint8_t*>(s);
+}
+
+// `S::params()`, or none if the scenario has no parameters.
+template <typename S, typename = void>
+struct ParamsOf {
+    static std::vector<RawParam> get() {
+        return {};
+    }
+};
+template <typename S>
+struct ParamsOf<S, std::void_t<decltype(S::params())>> {
+    static std::vector<RawParam> get() {
+        return S::params();
+    }
+};
+
+template <typename S>
+RawScenario sc(const char* name, RawScenarioKind kind) {
+    static const std::vector<RawParam> PARAMS = ParamsOf<S>::get();
+
+    auto new_ = [](RawScenarioInit init) noexcept -> Handle {
+        return new S(ScenarioInit{
+            Alloc(init.alloc),
+            init.percent,
+            init.metrics,
+            PARAMS.data(),
+            PARAMS.size(),
+            init.params,
+            init.seed,
+            init.validation,
+        });
     };
-    auto destroy = [](Handle handle) noexcept {
-        auto ptr = static_cast<T*>(handle);
-        delete ptr;
+    auto run = [](Handle handle) noexcept {
+        static_cast<S*>(handle)->run();
     };
-    auto add = [](Handle handle, uint64_t element) noexcept {
-        auto ptr = static_cast<T*>(handle);
-        ptr->add(element);
+    auto drop = [](Handle handle) noexcept {
+        delete static_cast<S*>(handle);
     };
-    auto sum_all = [](Handle handle) noexcept -> uint64_t {
-        auto ptr = static_cast<T*>(handle);
-        return ptr->sum_all();
+
+    return RawScenario{
+        str(name), strlen(name), new_, run, drop, kind, PARAMS.data(), PARAMS.size(),
     };
+}
+
+template <typename S>
+RawScenario sb(const char* name) {
+    return sc<S>(name, RawScenarioKind::Bench);
+}
 
-    return RawTestData{ reinterpret_cast<const unsigned char*>(name), strlen(name), create, destroy, add, sum_all };
+template <typename S>
+RawScenario sv(const char* name) {
+    return sc<S>(name, RawScenarioKind::Validation);
 }
 
-EXPORT RawLoadTestsResult load_tests() {
-    static RawTestData TESTS[] = {
-        sol<StdList>("std_list"),
-        sol<ManualList>("manual_list"),
+template <template <typename> class L>
+RawImpl list_impl(const char* name) {
+    static RawScenario SCENARIOS[] = {
+        // validation
+        sv<First<L<uint64_t>>>("first"),
+        sv<Last<L<uint64_t>>>("last"),
+        sv<Order<L<uint64_t>>>("order"),
+        sv<UseAfterDelete<L<uint64_t>>>("use_after_delete"),
+        sv<UseAfterDeleteAndReinsert<L<uint64_t>>>("use_after_delete_and_reinsert"),
+        // bench
+        sb<FindString<L<std::string>>>("find_string"),
+        sb<PushPages<L<Page>>>("push_pages"),
+        sb<IteratePages<L<Page>>>("iterate_pages"),
+        sb<AddFrontBack<L<uint64_t>>>("add_front_back"),
+        sb<SearchMiddle<L<uint64_t>>>("search_middle"),
+        sb<SumScenario<L<uint64_t>>>("sum"),
+        sb<IterateShuffled<L<uint64_t>>>("iterate_shuffled"),
+        sb<PushDeleteOneScenario<L<uint64_t>>>("push_delete_one"),
+        sb<PushScenario<L<uint64_t>>>("push"),
+        sb<Fragmentation<L<uint64_t>>>("fragmentation"),
+    };
+
+    return RawImpl{ str(name), strlen(name), SCENARIOS, sizeof(SCENARIOS) / sizeof(*SCENARIOS) };
+}
+
+EXPORT uint32_t api_version() {
+    return API_VERSION;
+}
+
+EXPORT RawLoadResult load_tests() {
+    static RawImpl LIST_IMPLS[] = {
+        list_impl<StdList>("std_list"),
+        list_impl<ManualList>("manual_list"),
+    };
+    return RawLoadResult{
+        API_MAGIC, API_VERSION, sizeof(RawLoadResult), LIST_IMPLS, sizeof(LIST_IMPLS) / sizeof(*LIST_IMPLS),
     };
-    return RawLoadTestsResult{ TESTS, sizeof(TESTS) / sizeof(*TESTS) };
 }
@@ -0,0 +1,589 @@
+#pragma once
+
+// C++ versions of the scenarios in rust_tests/src/scenarios.rs. They do the
+// same work as their Rust counterparts so that the rows are comparable.
+
+#include <algorithm>
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+#include "allocator.hpp"
+#include "rng.hpp"
+
+// `assert` is compiled out in release builds, these checks are not.
+#define CHECK(cond)                                                                                \
+    do {                                                                                           \
+        if (!(cond)) {                                                                             \
+            check_failed(#cond, __FILE__, __LINE__);                                               \
+        }                                                                                          \
+    } while (0)
+
+[[noreturn]] inline void check_failed(const char* cond, const char* file, int line) {
+    std::fprintf(stderr, "check failed: `%s` at %s:%d\n", cond, file, line);
+    std::abort();
+}
+
+// Like a panic in the Rust scenarios.
+[[noreturn]] inline void fail(const char* message) {
+    std::fprintf(stderr, "%s\n", message);
+    std::abort();
+}
+
+// A parameter of a scenario, see `RawParam` in tests_api.
+inline RawParam param_u64(const char* name, uint64_t default_value, uint64_t min, uint64_t max) {
+    RawParam param{};
+    param.name = reinterpret_cast<const uint8_t*>(name);
+    param.name_size = std::strlen(name);
+    param.kind = RawParamKind::U64;
+    param.default_value.u64 = default_value;
+    param.min.u64 = min;
+    param.max.u64 = max;
+    return param;
+}
+
+struct ScenarioInit {
+    Alloc alloc;
+    uint32_t percent;
+    const RawMetrics* metrics;
+    // The `params()` of the scenario and their values.
+    const RawParam* params;
+    size_t params_count;
+    const RawParamValue* values;
+    uint64_t seed;
+    const RawValidation* validation;
+
+    size_t index_of(const char* name) const {
+        for (size_t i = 0; i < params_count; ++i) {
+            const RawParam& param = params[i];
+            size_t size = std::strlen(name);
+            if (param.name_size == size && std::memcmp(param.name, name, size) == 0) {
+                return i;
+            }
+        }
+        std::fprintf(stderr, "no parameter `%s`\n", name);
+        std::abort();
+    }
+
+    uint64_t u64(const char* name) const {
+        size_t i = index_of(name);
+        CHECK(params[i].kind == RawParamKind::U64);
+        return values[i].u64;
+    }
+
+    // The parameter `name` scaled by the percent, but never below its minimum.
+    uint64_t scaled_u64(const char* name) const {
+        uint64_t value = u64(name) * percent / 100;
+        return std::max(value, params[index_of(name)].min.u64);
+    }
+
+    // See `RawMetrics` in tests_api.
+    void report(const char* name, double value) const {
+        auto bytes = reinterpret_cast<const uint8_t*>(name);
+        metrics->report(metrics->ctx, bytes, std::strlen(name), value);
+    }
+
+    // Whether `value` lives in freed memory, see `RawValidation` in tests_api.
+    template <typename T>
+    bool is_freed(const T* value) const {
+        auto ptr = reinterpret_cast<const uint8_t*>(value);
+        return validation->is_freed(validation->ctx, ptr, sizeof(T));
+    }
+};
+
+constexpr uint64_t ITERATIONS = 1'000'000;
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class SumScenario {
+    ScenarioInit init;
+    uint64_t iterations;
+    L list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("size", 10'000'000, 1, 1'000'000'000) };
+    }
+
+    explicit SumScenario(const ScenarioInit& init)
+        : init(init), iterations(init.scaled_u64("size")), list(init.alloc, iterations) {
+        for (uint64_t i = 1; i <= iterations; ++i) {
+            list.push_back(i);
+        }
+    }
+
+    void run() {
+        uint64_t sum = 0;
+        for (auto it = list.first(); it; it = list.next(*it)) {
+            const uint64_t* value = list.value(*it);
+            CHECK(value);
+            sum += *value;
+        }
+
+        CHECK(sum == iterations * (iterations + 1) / 2);
+        init.report("checksum", static_cast<double>(sum));
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class IterateShuffled {
+    ScenarioInit init;
+    uint64_t size;
+    L list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("size", 1'000'000, 1, 100'000'000) };
+    }
+
+    // Half of the nodes are deleted in random order and pushed again, so
+    // where freed memory is reused the order of the list no longer follows
+    // the order of the memory.
+    explicit IterateShuffled(const ScenarioInit& init)
+        : init(init), size(init.scaled_u64("size")), list(init.alloc, size) {
+        Rng rng(init.seed);
+        std::vector<std::pair<typename L::NodeRef, uint64_t>> nodes;
+        nodes.reserve(size);
+        for (uint64_t i = 1; i <= size; ++i) {
+            nodes.emplace_back(list.push_back(i), i);
+        }
+        rng.shuffle(nodes.data(), nodes.size());
+        for (size_t i = 0; i < nodes.size() / 2; ++i) {
+            list.remove(nodes[i].first);
+            list.push_back(nodes[i].second);
+        }
+    }
+
+    void run() {
+        uint64_t sum = 0;
+        for (auto it = list.first(); it; it = list.next(*it)) {
+            const uint64_t* value = list.value(*it);
+            CHECK(value);
+            sum += *value;
+        }
+
+        CHECK(sum == size * (size + 1) / 2);
+        init.report("checksum", static_cast<double>(sum));
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class PushDeleteOneScenario {
+    ScenarioInit init;
+    std::optional<L> list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("iterations", ITERATIONS, 1, 1'000'000'000) };
+    }
+
+    explicit PushDeleteOneScenario(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L& list = this->list.emplace(init.alloc, 1);
+        uint64_t iterations = init.scaled_u64("iterations");
+        for (uint64_t i = 1; i <= iterations; ++i) {
+            auto node = list.push_back(i);
+            list.remove(node);
+        }
+
+        CHECK(!list.first());
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class PushScenario {
+    ScenarioInit init;
+    std::optional<L> list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("size", 10'000'000, 1, 1'000'000'000) };
+    }
+
+    explicit PushScenario(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        uint64_t iterations = init.scaled_u64("size");
+        L& list = this->list.emplace(init.alloc, iterations);
+        for (uint64_t i = 1; i <= iterations; ++i) {
+            list.push_back(i);
+        }
+
+        CHECK(*list.value(*list.last()) == iterations);
+        init.report("nodes", static_cast<double>(iterations));
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class Fragmentation {
+    ScenarioInit init;
+    std::optional<L> list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return {
+            param_u64("rounds", 1'000, 1, 1'000'000),
+            param_u64("batch", 10'000, 1, 100'000'000),
+        };
+    }
+
+    explicit Fragmentation(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L& list = this->list.emplace(init.alloc, 1000);
+        uint64_t iterations = init.scaled_u64("rounds");
+        uint64_t batch = init.u64("batch");
+        uint64_t deleted = 0;
+        for (uint64_t j = 0; j <= iterations; ++j) {
+            std::vector<typename L::NodeRef> to_delete;
+            to_delete.reserve(iterations);
+
+            uint64_t m = 2;
+
+            for (uint64_t i = 0; i < batch; ++i) {
+                auto node = list.push_back(i);
+                if (i % m != 0) {
+                    continue;
+                }
+                to_delete.push_back(node);
+
+                m += 1;
+                if (m > 7) {
+                    m = 2;
+                }
+            }
+
+            deleted += to_delete.size();
+            for (auto node : to_delete) {
+                list.remove(node);
+            }
+        }
+        init.report("nodes deleted", static_cast<double>(deleted));
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class First {
+    ScenarioInit init;
+
+  public:
+    explicit First(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L list(init.alloc, 2);
+
+        auto node = list.push_front(0xDA);
+        list.push_back(5);
+        CHECK(*list.first() == node);
+        CHECK(*list.value(node) == 0xDA);
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class Last {
+    ScenarioInit init;
+
+  public:
+    explicit Last(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L list(init.alloc, 2);
+
+        list.push_front(5);
+        auto node = list.push_back(0xDA);
+        CHECK(*list.last() == node);
+        CHECK(*list.value(node) == 0xDA);
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class Order {
+    ScenarioInit init;
+
+  public:
+    explicit Order(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L list(init.alloc, 2);
+
+        auto n3 = list.push_front(3);
+        auto n2 = list.insert_before(n3, 2);
+        auto n1 = list.push_front(1);
+        auto n5 = list.push_back(5);
+        auto n4 = list.insert_before(n5, 4);
+
+        typename L::NodeRef values[] = { n1, n2, n3, n4, n5 };
+        uint64_t values_index = 0;
+
+        for (auto it = list.first(); it; it = list.next(*it)) {
+            uint64_t v = *list.value(*it);
+            uint64_t w = *list.value(values[values_index]);
+            CHECK(v == w);
+            CHECK(v == values_index + 1);
+            values_index += 1;
+        }
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class UseAfterDelete {
+    ScenarioInit init;
+
+  public:
+    explicit UseAfterDelete(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L list(init.alloc, 2);
+
+        auto node = list.push_front(0xDA);
+        list.remove(node);
+        // UB incoming
+        const uint64_t* value = list.value(node);
+        if (value) {
+            if (init.is_freed(value)) {
+                fail("read freed memory");
+            }
+            if (*value == 0xDA) {
+                fail("read used after free value");
+            }
+        }
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class UseAfterDeleteAndReinsert {
+    ScenarioInit init;
+
+  public:
+    explicit UseAfterDeleteAndReinsert(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        L list(init.alloc, 2);
+
+        auto node = list.push_front(0xDA);
+        list.remove(node);
+        list.push_front(0xDD);
+        // UB incoming
+        const uint64_t* value = list.value(node);
+        if (value) {
+            if (init.is_freed(value)) {
+                fail("read freed memory");
+            }
+            if (*value == 0xDD) {
+                fail("read used after reinsert with old node");
+            }
+        }
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class SearchMiddle {
+    ScenarioInit init;
+    uint64_t iterations;
+    uint64_t batch;
+    L list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return {
+            param_u64("size", 10'000'000, 2, 1'000'000'000),
+            param_u64("batch", 100'000, 2, 1'000'000'000),
+        };
+    }
+
+    explicit SearchMiddle(const ScenarioInit& init)
+        : init(init), iterations(init.scaled_u64("size")),
+          batch(init.scaled_u64("batch")), list(init.alloc, iterations) {
+        for (uint64_t i = 1, size = init.u64("size"); i <= size; ++i) {
+            list.push_back(i);
+        }
+    }
+
+    void run() {
+        for (uint64_t i = 1; i < iterations / batch; ++i) {
+            uint64_t to_find = i * batch;
+            auto node = list.search([to_find](const uint64_t& x) { return x == to_find; });
+            CHECK(node);
+
+            CHECK(*list.value(*node) == to_find);
+            CHECK(*list.value(*list.prec(*node)) == to_find - 1);
+            CHECK(*list.value(*list.next(*node)) == to_find + 1);
+        }
+        uint64_t searches = iterations / batch;
+        init.report("searches", static_cast<double>(searches > 0 ? searches - 1 : 0));
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class AddFrontBack {
+    ScenarioInit init;
+    std::optional<L> list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("iterations", 1'000'000, 1, 1'000'000'000) };
+    }
+
+    explicit AddFrontBack(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        uint64_t iterations = init.scaled_u64("iterations");
+        L& list = this->list.emplace(init.alloc, iterations);
+
+        for (uint64_t i = 0; i < iterations; ++i) {
+            list.push_back(i);
+            list.push_front(i);
+        }
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+struct alignas(4096) Page {
+    uint8_t data[4096];
+
+    static Page numbered() {
+        Page page;
+        for (size_t i = 0; i < sizeof(page.data); ++i) {
+            page.data[i] = static_cast<uint8_t>(i);
+        }
+        return page;
+    }
+
+    uint64_t sum() const {
+        uint64_t sum = 0;
+        for (uint8_t x : data) {
+            sum += x;
+        }
+        return sum;
+    }
+};
+
+template <typename L>
+class PushPages {
+    ScenarioInit init;
+    std::optional<L> list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("pages", 1'000, 1, 1'000'000) };
+    }
+
+    explicit PushPages(const ScenarioInit& init) : init(init) {
+    }
+
+    void run() {
+        uint64_t iterations = init.scaled_u64("pages");
+        L& list = this->list.emplace(init.alloc, iterations);
+        Page page = Page::numbered();
+
+        for (uint64_t i = 0; i < iterations; ++i) {
+            list.push_back(page);
+            list.push_front(page);
+        }
+    }
+};
+
+template <typename L>
+class IteratePages {
+    L list;
+    uint64_t sum_one;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("pages", 1'000, 1, 1'000'000) };
+    }
+
+    explicit IteratePages(const ScenarioInit& init)
+        : list(init.alloc, init.scaled_u64("pages")) {
+        uint64_t iterations = init.scaled_u64("pages");
+        Page page = Page::numbered();
+        sum_one = page.sum();
+        for (uint64_t i = 0; i < iterations; ++i) {
+            list.push_back(page);
+            list.push_front(page);
+        }
+    }
+
+    void run() {
+        for (auto it = list.first(); it; it = list.next(*it)) {
+            const Page* value = list.value(*it);
+            CHECK(value);
+            CHECK(value->sum() == sum_one);
+        }
+    }
+};
+
+// ----------------------------------------------------------------------------
+
+template <typename L>
+class FindString {
+    ScenarioInit init;
+    uint64_t iterations;
+    L list;
+
+  public:
+    static std::vector<RawParam> params() {
+        return { param_u64("strings", 10'000, 1, 1'000'000) };
+    }
+
+    explicit FindString(const ScenarioInit& init)
+        : init(init), iterations(init.scaled_u64("strings")),
+          list(init.alloc, iterations) {
+        std::string s;
+        s.reserve(4096);
+        for (uint64_t i = 0; i < iterations; ++i) {
+            s += "abc";
+            list.push_back(s);
+        }
+    }
+
+    void run() {
+        std::string s;
+        s.reserve(4096);
+        for (uint64_t i = 0; i < iterations; ++i) {
+            s += "abc";
+
+            auto node = list.search([&s](const std::string& x) { return x == s; });
+            CHECK(node);
+        }
+        init.report("searches", static_cast<double>(iterations));
+    }
+};
@@ -0,0 +1,121 @@
+#pragma once
+
+#include <cstddef>
+#include <utility>
+#include "../allocator.hpp"
+#include "../double_linked_list.hpp"
+
+template <typename T>
+struct ManualNode {
+    T value;
+    ManualNode* prec;
+    ManualNode* next;
+};
+
+template <typename T>
+class ManualList : public DoubleLinkedList<ManualList<T>, T, ManualNode<T>*> {
+    using Node = ManualNode<T>;
+
+    Alloc alloc;
+    Node* head = nullptr;
+    Node* tail = nullptr;
+
+    static std::optional<Node*> some(Node* node) {
+        if (node) {
+            return node;
+        }
+        return std::nullopt;
+    }
+
+  public:
+    using NodeRef = Node*;
+
+    ManualList(Alloc alloc, size_t) : alloc(alloc) {
+    }
+    ManualList(const ManualList&) = delete;
+    ManualList& operator=(const ManualList&) = delete;
+
+    ~ManualList() {
+        while (head) {
+            Node* temp = head;
+            head = head->next;
+            alloc.destroy(temp);
+        }
+    }
+
+    NodeRef insert_after(NodeRef node, T value) {
+        Node* new_node = alloc.create<Node>(std::move(value), node, node->next);
+        if (node->next) {
+            node->next->prec = new_node;
+        } else {
+            tail = new_node;
+        }
+        node->next = new_node;
+        return new_node;
+    }
+
+    NodeRef insert_before(NodeRef node, T value) {
+        Node* new_node = alloc.create<Node>(std::move(value), node->prec, node);
+        if (node->prec) {
+            node->prec->next = new_node;
+        } else {
+            head = new_node;
+        }
+        node->prec = new_node;
+        return new_node;
+    }
+
+    NodeRef push_back(T value) {
+        if (!tail) {
+            head = tail = alloc.create<Node>(std::move(value), nullptr, nullptr);
+            return tail;
+        }
+        return insert_after(tail, std::move(value));
+    }
+
+    NodeRef push_front(T value) {
+        if (!head) {
+            head = tail = alloc.create<Node>(std::move(value), nullptr, nullptr);
+            return head;
+        }
+        return insert_before(head, std::move(value));
+    }
+
+    void remove(NodeRef node) {
+        if (node->prec) {
+            node->prec->next = node->next;
+        } else {
+            head = node->next;
+        }
+        if (node->next) {
+            node->next->prec = node->prec;
+        } else {
+            tail = node->prec;
+        }
+        alloc.destroy(node);
+    }
+
+    std::optional<NodeRef> next(NodeRef node) const {
+        return some(node->next);
+    }
+
+    std::optional<NodeRef> prec(NodeRef node) const {
+        return some(node->prec);
+    }
+
+    std::optional<NodeRef> first() const {
+        return some(head);
+    }
+
+    std::optional<NodeRef> last() const {
+        return some(tail);
+    }
+
+    const T* value(NodeRef node) const {
+        return &node->value;
+    }
+
+    T* value_mut(NodeRef node) {
+        return &node->value;
+    }
+};
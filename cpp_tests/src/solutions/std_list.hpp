@@ -0,0 +1,78 @@
+#pragma once
+
+#include <cstddef>
+#include <iterator>
+#include <list>
+#include <utility>
+#include "../allocator.hpp"
+#include "../double_linked_list.hpp"
+
+template <typename T>
+class StdList
+    : public DoubleLinkedList<StdList<T>, T, typename std::list<T, StlAlloc<T>>::iterator> {
+    // `mutable` so that the const accessors can hand out non-const iterators.
+    mutable std::list<T, StlAlloc<T>> list;
+
+  public:
+    using NodeRef = typename std::list<T, StlAlloc<T>>::iterator;
+
+    StdList(Alloc alloc, size_t) : list(StlAlloc<T>(alloc)) {
+    }
+
+    NodeRef insert_after(NodeRef node, T value) {
+        return list.insert(std::next(node), std::move(value));
+    }
+
+    NodeRef insert_before(NodeRef node, T value) {
+        return list.insert(node, std::move(value));
+    }
+
+    NodeRef push_back(T value) {
+        return list.insert(list.end(), std::move(value));
+    }
+
+    NodeRef push_front(T value) {
+        return list.insert(list.begin(), std::move(value));
+    }
+
+    void remove(NodeRef node) {
+        list.erase(node);
+    }
+
+    std::optional<NodeRef> next(NodeRef node) const {
+        auto next = std::next(node);
+        if (next == list.end()) {
+            return std::nullopt;
+        }
+        return next;
+    }
+
+    std::optional<NodeRef> prec(NodeRef node) const {
+        if (node == list.begin()) {
+            return std::nullopt;
+        }
+        return std::prev(node);
+    }
+
+    std::optional<NodeRef> first() const {
+        if (list.empty()) {
+            return std::nullopt;
+        }
+        return list.begin();
+    }
+
+    std::optional<NodeRef> last() const {
+        if (list.empty()) {
+            return std::nullopt;
+        }
+        return std::prev(list.end());
+    }
+
+    const T* value(NodeRef node) const {
+        return &*node;
+    }
+
+    T* value_mut(NodeRef node) {
+        return &*node;
+    }
+};
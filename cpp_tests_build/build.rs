@@ -1,4 +1,4 @@
-use std::{fs, path::Path, process::Command};
+use std::{env, fs, path::Path, process::Command};
 
 fn spawn_ok(cmd: &mut Command) {
     let code = cmd.status().unwrap();
@@ -40,10 +40,11 @@ fn compile_cpp() {
         );
     };
 
-    fs::create_dir_all("../target/Debug").unwrap();
-    fs::create_dir_all("../target/Release").unwrap();
-    fs::copy(in_path, format!("../target/Debug/{dl_name}")).unwrap();
-    fs::copy(in_path, format!("../target/Release/{dl_name}")).unwrap();
+    // OUT_DIR is `target/<profile>/build/<pkg>-<hash>/out`; the library goes
+    // next to the runner executable in `target/<profile>`.
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let profile_dir = Path::new(&out_dir).ancestors().nth(3).unwrap();
+    fs::copy(in_path, profile_dir.join(dl_name)).unwrap();
 
     println!("cargo::rerun-if-changed=../cpp_tests");
 }
@@ -51,6 +52,7 @@ fn compile_cpp() {
 fn generate_header() {
     cbindgen::Builder::new()
         .with_crate("../tests_api")
+        .with_pragma_once(true)
         .generate()
         .unwrap()
         .write_to_file("../cpp_tests/src/api.hpp");
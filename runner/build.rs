@@ -0,0 +1,140 @@
+//! Bakes what the run manifest needs to know about the build into the runner,
+//! see `src/manifest.rs`.
+
+use std::{env, fs, path::Path, process::Command};
+
+fn command(program: &str, args: &[&str], dir: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The value of `key` in the `[section]` of a Cargo.toml, without quotes.
+/// Good enough for the flat tables we look at; this avoids a toml dependency.
+fn toml_value(toml: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in toml.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == format!("[{section}]");
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            if k.trim() == key {
+                return Some(v.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+/// The version cargo resolved `package` to and the features it enabled, from
+/// `cargo metadata`. Those are unified over the whole workspace, which is what
+/// a workspace build of the runner gets.
+fn resolved(root: &Path, package: &str) -> Option<(String, Vec<String>)> {
+    let cargo = env::var("CARGO").ok()?;
+    let args = ["metadata", "--format-version", "1", "--offline"];
+    let metadata: serde_json::Value = serde_json::from_str(&command(&cargo, &args, root)?).ok()?;
+    let package = metadata["packages"]
+        .as_array()?
+        .iter()
+        .find(|x| x["name"] == package)?;
+    let node = metadata["resolve"]["nodes"]
+        .as_array()?
+        .iter()
+        .find(|x| x["id"] == package["id"])?;
+    let features = node["features"]
+        .as_array()?
+        .iter()
+        .filter_map(|x| Some(x.as_str()?.to_string()))
+        .collect();
+    Some((package["version"].as_str()?.to_string(), features))
+}
+
+fn set(key: &str, value: &str) {
+    println!("cargo:rustc-env=RUNNER_{key}={value}");
+}
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let root = Path::new(&manifest_dir).parent().unwrap();
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command(&rustc, &["-V"], root);
+    set("RUSTC", rustc_version.as_deref().unwrap_or("unknown"));
+    set("TARGET", &env::var("TARGET").unwrap());
+    set("OPT_LEVEL", &env::var("OPT_LEVEL").unwrap());
+    set("DEBUG", &env::var("DEBUG").unwrap());
+
+    // `PROFILE` is `debug` or `release` even for custom profiles, which is
+    // the best cargo tells build scripts.
+    let profile = env::var("PROFILE").unwrap();
+    set("PROFILE", &profile);
+    let section = match profile.as_str() {
+        "debug" => "profile.dev",
+        _ => "profile.release",
+    };
+    let workspace_toml = root.join("Cargo.toml");
+    println!("cargo:rerun-if-changed={}", workspace_toml.display());
+    let toml = fs::read_to_string(&workspace_toml).unwrap_or_default();
+    let lto = toml_value(&toml, section, "lto");
+    set("LTO", lto.as_deref().unwrap_or("false"));
+    match resolved(root, "snmalloc-sys") {
+        Some((version, features)) => {
+            set("SNMALLOC", &version);
+            set("SNMALLOC_FEATURES", &features.join(","));
+        }
+        None => {
+            let snmalloc = toml_value(&toml, "workspace.dependencies", "snmalloc-sys");
+            set("SNMALLOC", snmalloc.as_deref().unwrap_or("unknown"));
+            set("SNMALLOC_FEATURES", "unknown");
+        }
+    }
+
+    let commit = command("git", &["rev-parse", "HEAD"], root);
+    set("GIT_COMMIT", commit.as_deref().unwrap_or("unknown"));
+    let status = command(
+        "git",
+        &["status", "--porcelain", "--untracked-files=no"],
+        root,
+    );
+    set(
+        "GIT_DIRTY",
+        &status.is_some_and(|x| !x.is_empty()).to_string(),
+    );
+    // A commit moves the branch HEAD points to, not HEAD itself.
+    let head = fs::read_to_string(root.join(".git/HEAD")).unwrap_or_default();
+    let branch = head
+        .strip_prefix("ref: ")
+        .map(|x| format!(".git/{}", x.trim()));
+    // Editing a tracked file without staging it touches neither of the git
+    // files, so the sources the runner is built from are watched as well.
+    let sources = [
+        "runner",
+        "rust_tests",
+        "tests_api",
+        "std_stuff",
+        "cpp_tests",
+        "cpp_tests_build",
+    ];
+    for i in [".git/HEAD", ".git/index"]
+        .into_iter()
+        .chain(sources)
+        .map(String::from)
+        .chain(branch)
+    {
+        let path = root.join(i);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
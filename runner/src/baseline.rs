@@ -0,0 +1,287 @@
+use crate::export::{self, OutputFormat, ResultRecord, ResultSet};
+use anyhow::{bail, Context, Result};
+use ascii_table::{Align, AsciiTable};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Only the results of a baseline and the API version are read back; the
+/// rest of the metadata is informative.
+#[derive(Deserialize)]
+struct Baseline {
+    metadata: BaselineMetadata,
+    results: Vec<ResultRecord>,
+}
+
+#[derive(Deserialize)]
+struct BaselineMetadata {
+    /// Missing in baselines saved before it was recorded.
+    api_version: Option<u32>,
+}
+
+type Key<'x> = (&'x str, &'x str, &'x str, u32);
+
+fn key(x: &ResultRecord) -> Key<'_> {
+    (&x.scenario, &x.impl_name, &x.allocator, x.percent)
+}
+
+fn path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.json"))
+}
+
+pub fn save(dir: &Path, name: &str, set: &ResultSet) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("couldn't create baseline directory `{}`", dir.display()))?;
+    export::write_results(&path(dir, name), Some(OutputFormat::Json), set, false)
+}
+
+fn load(dir: &Path, name: &str) -> Result<Baseline> {
+    let path = path(dir, name);
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("couldn't read baseline `{}`", path.display()))?;
+    let baseline = serde_json::from_str(&data)
+        .with_context(|| format!("couldn't parse baseline `{}`", path.display()))?;
+    Ok(baseline)
+}
+
+/// The percent column is only there if `with_percent`, e.g. for a sweep.
+fn create_table(with_percent: bool) -> AsciiTable {
+    let mut ascii_table = AsciiTable::default();
+    ascii_table.set_max_width(200);
+
+    let mut columns = vec![
+        ("scenario", Align::Center),
+        ("name", Align::Center),
+        ("allocator", Align::Center),
+    ];
+    if with_percent {
+        columns.push(("percent", Align::Right));
+    }
+    columns.extend([
+        ("baseline", Align::Right),
+        ("current", Align::Right),
+        ("delta", Align::Right),
+        ("status", Align::Left),
+    ]);
+
+    for (index, (name, alignment)) in columns.iter().enumerate() {
+        ascii_table
+            .column(index)
+            .set_header(*name)
+            .set_align(*alignment);
+    }
+
+    ascii_table
+}
+
+/// The delta table of a comparison and what it adds up to.
+struct Comparison {
+    rows: Vec<Vec<String>>,
+    with_percent: bool,
+    regressions: usize,
+    /// Baseline results that weren't run this time, e.g. filtered out.
+    missing: usize,
+}
+
+/// Compares the median run times of `set` against the baseline `name` and
+/// prints a delta table. Returns the number of regressions above `threshold`
+/// percent.
+pub fn compare(dir: &Path, name: &str, set: &ResultSet, threshold: f64) -> Result<usize> {
+    let baseline = load(dir, name)?;
+    check_api_version(name, &baseline, set.metadata.api_version)?;
+
+    let comparison = compare_results(&baseline.results, &set.results, threshold);
+    println!("comparison against baseline `{name}` (threshold {threshold}%):");
+    create_table(comparison.with_percent).print(comparison.rows.iter());
+    if comparison.missing > 0 {
+        println!("{} baseline result(s) weren't run", comparison.missing);
+    }
+    if comparison.regressions > 0 {
+        println!(
+            "{} regression(s) above {threshold}%",
+            comparison.regressions
+        );
+    }
+
+    Ok(comparison.regressions)
+}
+
+fn check_api_version(name: &str, baseline: &Baseline, api_version: u32) -> Result<()> {
+    match baseline.metadata.api_version {
+        Some(version) if version != api_version => bail!(
+            "baseline `{name}` was saved with API version {version}, not \
+             {api_version}; its results aren't comparable"
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn compare_results(
+    baseline: &[ResultRecord],
+    results: &[ResultRecord],
+    threshold: f64,
+) -> Comparison {
+    let baseline: IndexMap<Key, &ResultRecord> = baseline.iter().map(|x| (key(x), x)).collect();
+    let current: HashSet<Key> = results.iter().map(key).collect();
+    let missing = baseline.keys().filter(|x| !current.contains(*x)).count();
+
+    let with_percent = results.iter().any(|x| x.percent != results[0].percent);
+
+    let mut regressions = 0;
+    let mut output: Vec<Vec<String>> = Vec::with_capacity(results.len());
+    for current in results.iter() {
+        let ns = |x: u64| format!("{:?}", Duration::from_nanos(x));
+        let new = current.run_time.median_ns;
+
+        let old = baseline.get(&key(current)).filter(|x| x.status.is_ok());
+        let (old, delta, status) = match old {
+            // A scenario that crashes now hides any timing, so it counts as a
+            // regression unless it never finished in the baseline either.
+            _ if !current.status.is_ok() => {
+                if old.is_some() {
+                    regressions += 1;
+                }
+                let status = format!("FAILED ({})", current.status);
+                ("-".to_string(), "-".to_string(), status)
+            }
+            Some(old) => {
+                let old = old.run_time.median_ns;
+                let delta = (new as f64 - old as f64) / old.max(1) as f64 * 100.0;
+                let status = if delta > threshold {
+                    regressions += 1;
+                    "REGRESSION"
+                } else if delta < -threshold {
+                    "improvement"
+                } else {
+                    "ok"
+                };
+                (ns(old), format!("{delta:+.2}%"), status.to_string())
+            }
+            None => ("-".to_string(), "-".to_string(), "new".to_string()),
+        };
+
+        let mut row = vec![
+            current.scenario.clone(),
+            current.impl_name.clone(),
+            current.allocator.clone(),
+        ];
+        if with_percent {
+            row.push(format!("{}%", current.percent));
+        }
+        row.extend([
+            old,
+            if current.status.is_ok() {
+                ns(new)
+            } else {
+                "-".to_string()
+            },
+            delta,
+            status,
+        ]);
+        output.push(row);
+    }
+
+    Comparison {
+        rows: output,
+        with_percent,
+        regressions,
+        missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export::TimeStats, isolate::Status, stats::Metrics};
+
+    fn record(scenario: &str, median_ns: u64, status: Status) -> ResultRecord {
+        ResultRecord {
+            scenario: scenario.to_string(),
+            impl_name: "rust_rc_impl".to_string(),
+            allocator: "system".to_string(),
+            percent: 100,
+            samples: 1,
+            status,
+            setup_time: TimeStats::default(),
+            run_time: TimeStats {
+                median_ns,
+                ..TimeStats::default()
+            },
+            teardown_time: TimeStats::default(),
+            alloc_time: TimeStats::default(),
+            no_allocs: 0,
+            no_reallocs: 0,
+            bytes_copied: 0,
+            max_memory: 0,
+            metrics: Metrics::default(),
+        }
+    }
+
+    fn status(comparison: &Comparison) -> Vec<&str> {
+        comparison
+            .rows
+            .iter()
+            .map(|x| x.last().unwrap().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn regressions_are_above_the_threshold() {
+        let baseline = [
+            record("sum", 1000, Status::Ok),
+            record("push", 1000, Status::Ok),
+            record("search_middle", 1000, Status::Ok),
+            record("fragmentation", 1000, Status::Ok),
+        ];
+        let results = [
+            record("sum", 1050, Status::Ok),
+            record("push", 1051, Status::Ok),
+            record("search_middle", 900, Status::Ok),
+            record("fragmentation", 1000, Status::Signal(libc::SIGSEGV)),
+        ];
+        let comparison = compare_results(&baseline, &results, 5.0);
+        assert_eq!(
+            status(&comparison),
+            ["ok", "REGRESSION", "improvement", "FAILED (SIGSEGV)"]
+        );
+        assert_eq!(comparison.rows[1][5], "+5.10%");
+        assert_eq!(comparison.regressions, 2);
+        assert!(!comparison.with_percent);
+    }
+
+    #[test]
+    fn results_missing_on_either_side_are_no_regressions() {
+        let baseline = [
+            record("sum", 1000, Status::Ok),
+            record("push", 1000, Status::Ok),
+            record("order", 1000, Status::Exit(1)),
+        ];
+        let results = [
+            record("sum", 1000, Status::Ok),
+            record("iterate_shuffled", 1000, Status::Ok),
+            record("order", 1000, Status::Exit(1)),
+        ];
+        let comparison = compare_results(&baseline, &results, 5.0);
+        assert_eq!(status(&comparison), ["ok", "new", "FAILED (exit 1)"]);
+        assert_eq!(comparison.regressions, 0);
+        assert_eq!(comparison.missing, 1);
+    }
+
+    #[test]
+    fn baselines_of_other_api_versions_are_rejected() {
+        let parse = |json: &str| serde_json::from_str::<Baseline>(json).unwrap();
+        let baseline = parse(r#"{"metadata": {"api_version": 5}, "results": []}"#);
+        assert!(check_api_version("old", &baseline, 5).is_ok());
+        let error = check_api_version("old", &baseline, 6).unwrap_err();
+        assert!(error.to_string().contains("API version 5, not 6"));
+
+        // Older baselines don't know their version.
+        let baseline = parse(r#"{"metadata": {}, "results": []}"#);
+        assert!(check_api_version("older", &baseline, 6).is_ok());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{
+    isolate::Status,
+    manifest::Manifest,
+    stats::{self, Metrics, Summary},
+    tables, Results, TestResult,
+};
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    Latex,
+    Markdown,
+}
+impl OutputFormat {
+    /// Picks the format from the extension of `path`, if it has a known one.
+    pub fn from_path(path: &Path) -> Option<OutputFormat> {
+        match path.extension()?.to_str()? {
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "tex" => Some(OutputFormat::Latex),
+            "md" => Some(OutputFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RunMetadata {
+    pub runner_version: &'static str,
+    /// The `tests_api` ABI the scenarios were built against; results of
+    /// different versions aren't comparable.
+    pub api_version: u32,
+    pub timestamp: u64,
+    pub allocators: Vec<&'static str>,
+    pub percents: Vec<u32>,
+    pub bench: bool,
+    pub validation: bool,
+    pub repeat: usize,
+    pub warmup: usize,
+    pub isolate: bool,
+    pub timeout_secs: Option<f64>,
+    pub pin_cpu: Option<usize>,
+    pub nice: Option<i32>,
+    pub realtime: Option<i32>,
+    /// The `--param` overrides.
+    pub params: Vec<String>,
+    pub seed: u64,
+    pub impls: usize,
+    pub scenarios: usize,
+    pub manifest: Manifest,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct TimeStats {
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+    pub stddev_ns: u64,
+    pub p95_ns: u64,
+}
+impl From<&Summary> for TimeStats {
+    fn from(x: &Summary) -> Self {
+        TimeStats {
+            min_ns: x.min.as_nanos() as u64,
+            median_ns: x.median.as_nanos() as u64,
+            mean_ns: x.mean.as_nanos() as u64,
+            stddev_ns: x.stddev.as_nanos() as u64,
+            p95_ns: x.p95.as_nanos() as u64,
+        }
+    }
+}
+impl From<&TimeStats> for Summary {
+    fn from(x: &TimeStats) -> Self {
+        Summary {
+            min: Duration::from_nanos(x.min_ns),
+            median: Duration::from_nanos(x.median_ns),
+            mean: Duration::from_nanos(x.mean_ns),
+            stddev: Duration::from_nanos(x.stddev_ns),
+            p95: Duration::from_nanos(x.p95_ns),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ResultRecord {
+    pub scenario: String,
+    pub impl_name: String,
+    pub allocator: String,
+    pub percent: u32,
+    pub samples: usize,
+    /// Missing in results saved before `--isolate` existed.
+    #[serde(default)]
+    pub status: Status,
+    /// Missing in results saved before setup and teardown were timed.
+    #[serde(default)]
+    pub setup_time: TimeStats,
+    pub run_time: TimeStats,
+    #[serde(default)]
+    pub teardown_time: TimeStats,
+    pub alloc_time: TimeStats,
+    pub no_allocs: usize,
+    /// Missing in results saved before reallocs were counted.
+    #[serde(default)]
+    pub no_reallocs: usize,
+    #[serde(default)]
+    pub bytes_copied: usize,
+    pub max_memory: usize,
+    /// What the scenario reported itself; missing in older results.
+    #[serde(default, with = "stats::metrics_serde")]
+    pub metrics: Metrics,
+}
+impl From<&TestResult<'_>> for ResultRecord {
+    fn from(x: &TestResult) -> Self {
+        ResultRecord {
+            scenario: x.scenario.to_string(),
+            impl_name: x.impl_name.to_string(),
+            allocator: x.allocator.to_string(),
+            percent: x.percent,
+            samples: x.samples,
+            status: x.status,
+            setup_time: TimeStats::from(&x.setup_time),
+            run_time: TimeStats::from(&x.run_time),
+            teardown_time: TimeStats::from(&x.teardown_time),
+            alloc_time: TimeStats::from(&x.alloc_time),
+            no_allocs: x.no_allocs,
+            no_reallocs: x.no_reallocs,
+            bytes_copied: x.bytes_copied,
+            max_memory: x.max_memory,
+            metrics: x.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResultSet {
+    pub metadata: RunMetadata,
+    pub results: Vec<ResultRecord>,
+}
+impl ResultSet {
+    pub fn new(metadata: RunMetadata, results: &Results) -> ResultSet {
+        let results = results.values().flatten().map(ResultRecord::from).collect();
+        ResultSet { metadata, results }
+    }
+
+    /// Every metric name reported by any result, sorted.
+    pub fn metric_names(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self
+            .results
+            .iter()
+            .flat_map(|x| x.metrics.keys().map(|x| x.as_str()))
+            .collect();
+        names.into_iter().collect()
+    }
+}
+
+const CSV_HEADER: [&str; 30] = [
+    "scenario",
+    "impl",
+    "allocator",
+    "percent",
+    "samples",
+    "status",
+    "setup_min_ns",
+    "setup_median_ns",
+    "setup_mean_ns",
+    "setup_stddev_ns",
+    "setup_p95_ns",
+    "run_min_ns",
+    "run_median_ns",
+    "run_mean_ns",
+    "run_stddev_ns",
+    "run_p95_ns",
+    "teardown_min_ns",
+    "teardown_median_ns",
+    "teardown_mean_ns",
+    "teardown_stddev_ns",
+    "teardown_p95_ns",
+    "alloc_min_ns",
+    "alloc_median_ns",
+    "alloc_mean_ns",
+    "alloc_stddev_ns",
+    "alloc_p95_ns",
+    "no_allocs",
+    "no_reallocs",
+    "bytes_copied",
+    "max_memory",
+];
+
+fn csv_time_stats(x: &TimeStats) -> String {
+    format!(
+        "{},{},{},{},{}",
+        x.min_ns, x.median_ns, x.mean_ns, x.stddev_ns, x.p95_ns
+    )
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Writes nested objects as `# outer.inner: value` lines.
+fn write_csv_metadata(out: &mut impl Write, prefix: &str, value: &serde_json::Value) -> Result<()> {
+    match value {
+        serde_json::Value::Object(x) => {
+            for (key, value) in x {
+                write_csv_metadata(out, &format!("{prefix}{key}."), value)?;
+            }
+        }
+        serde_json::Value::String(s) => writeln!(out, "# {}: {s}", prefix.trim_end_matches('.'))?,
+        _ => writeln!(out, "# {}: {value}", prefix.trim_end_matches('.'))?,
+    }
+    Ok(())
+}
+
+fn write_csv(out: &mut impl Write, set: &ResultSet) -> Result<()> {
+    // Metadata goes in `#` comment lines so that the rows stay a plain table.
+    let metadata = serde_json::to_value(&set.metadata)?;
+    write_csv_metadata(out, "", &metadata)?;
+
+    // Metrics get a `metric:<name>` column each, empty where not reported.
+    let metric_names = set.metric_names();
+    let mut header: Vec<String> = CSV_HEADER.iter().map(|x| x.to_string()).collect();
+    header.extend(
+        metric_names
+            .iter()
+            .map(|x| csv_field(&format!("metric:{x}"))),
+    );
+    writeln!(out, "{}", header.join(","))?;
+    for i in set.results.iter() {
+        write!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&i.scenario),
+            csv_field(&i.impl_name),
+            csv_field(&i.allocator),
+            i.percent,
+            i.samples,
+            csv_field(&i.status.to_string()),
+            csv_time_stats(&i.setup_time),
+            csv_time_stats(&i.run_time),
+            csv_time_stats(&i.teardown_time),
+            csv_time_stats(&i.alloc_time),
+            i.no_allocs,
+            i.no_reallocs,
+            i.bytes_copied,
+            i.max_memory
+        )?;
+        for name in metric_names.iter() {
+            match i.metrics.get(*name) {
+                Some(value) => write!(out, ",{value}")?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes `set` to `path`. `grouped` splits the LaTeX and Markdown tables by
+/// scenario; the other formats ignore it.
+pub fn write_results(
+    path: &Path,
+    format: Option<OutputFormat>,
+    set: &ResultSet,
+    grouped: bool,
+) -> Result<()> {
+    let format = match format.or_else(|| OutputFormat::from_path(path)) {
+        Some(x) => x,
+        None if path.extension().is_none() => OutputFormat::Json,
+        None => bail!(
+            "can't guess the output format of `{}`; use --format",
+            path.display()
+        ),
+    };
+
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, set)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(&mut out, set)?,
+        OutputFormat::Latex => tables::write_latex(&mut out, set, grouped)?,
+        OutputFormat::Markdown => tables::write_markdown(&mut out, set, grouped)?,
+    }
+    out.flush()?;
+
+    println!("results written to {}", path.display());
+    Ok(())
+}
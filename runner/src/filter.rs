@@ -0,0 +1,118 @@
+/// A comma separated list of patterns given to `--impl-name` or `--scenario`.
+/// Patterns may use `*` and `?` as wildcards; a pattern starting with `!`
+/// excludes what it matches. Without any including pattern everything that
+/// isn't excluded is selected.
+pub struct Filter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    pub fn parse(s: Option<&str>) -> Filter {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for i in s.unwrap_or_default().split(',') {
+            let i = i.trim();
+            match i.strip_prefix('!') {
+                Some(x) => exclude.push(x.to_string()),
+                None if i.is_empty() => {}
+                None => include.push(i.to_string()),
+            }
+        }
+        Filter { include, exclude }
+    }
+
+    /// Whether something known under any of `names` is selected; impls can be
+    /// matched with or without the prefix of the library they come from.
+    pub fn matches(&self, names: &[&str]) -> bool {
+        let any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|x| names.iter().any(|name| glob(x, name)))
+        };
+        (self.include.is_empty() || any(&self.include)) && !any(&self.exclude)
+    }
+
+    /// The first including pattern that matches none of `names`.
+    pub fn unmatched(&self, names: &[&str]) -> Option<&str> {
+        self.include
+            .iter()
+            .find(|x| !names.iter().any(|name| glob(x, name)))
+            .map(|x| x.as_str())
+    }
+}
+
+/// Matches `name` against `pattern`, where `*` matches any run of characters
+/// and `?` exactly one.
+fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Classic backtracking over the last `*` seen.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_backtracks_over_star() {
+        assert!(glob("*ab", "aab"));
+        assert!(glob("a*b*c", "axbybc"));
+        assert!(glob("rust_*_list", "rust_rc_list_list"));
+        assert!(!glob("a*c", "abcbd"));
+        assert!(glob("a?c*", "abc"));
+        assert!(!glob("a?c", "ac"));
+        assert!(glob("**", ""));
+    }
+
+    #[test]
+    fn only_exclusions_select_the_rest() {
+        let filter = Filter::parse(Some("!rust_*,!cpp_std"));
+        assert!(filter.matches(&["cpp_manual"]));
+        assert!(!filter.matches(&["rust_rc"]));
+        assert!(!filter.matches(&["cpp_std"]));
+        assert_eq!(filter.unmatched(&[]), None);
+    }
+
+    #[test]
+    fn empty_items_are_ignored() {
+        let filter = Filter::parse(Some(" a, ,,b,"));
+        assert!(filter.matches(&["a"]));
+        assert!(filter.matches(&["b"]));
+        assert!(!filter.matches(&["c"]));
+        assert_eq!(filter.unmatched(&["a"]), Some("b"));
+        assert!(Filter::parse(Some("")).matches(&["c"]));
+        assert!(Filter::parse(None).matches(&["c"]));
+    }
+
+    #[test]
+    fn any_name_can_match() {
+        let filter = Filter::parse(Some("rc,!cpp_*"));
+        assert!(filter.matches(&["rust_rc", "rc"]));
+        assert!(!filter.matches(&["cpp_rc", "rc"]));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::export::ResultRecord;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    fmt::{self, Display},
+    io::Read,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Prefix of the line a child prints its result on; everything else it
+/// prints is ignored by the parent.
+pub const RESULT_MARKER: &str = "@runner-result ";
+
+/// How the process that ran a scenario ended.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Ok,
+    /// Killed by a signal, e.g. an abort after a failed check or a segfault.
+    Signal(i32),
+    /// Exited with a non-zero code, or with zero but without a result.
+    Exit(i32),
+    /// Killed by the runner after running longer than `--timeout`.
+    Timeout,
+}
+impl Status {
+    pub fn is_ok(self) -> bool {
+        self == Status::Ok
+    }
+}
+impl Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Ok => write!(f, "ok"),
+            Status::Signal(x) => match signal_name(x) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "signal {x}"),
+            },
+            Status::Exit(x) => write!(f, "exit {x}"),
+            Status::Timeout => write!(f, "TIMEOUT"),
+        }
+    }
+}
+
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        15 => "SIGTERM",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_: ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Which scenario of which impl a child runs, and how.
+pub struct ChildTask<'x> {
+    pub allocator: &'x str,
+    pub percent: u32,
+    pub impl_name: &'x str,
+    pub scenario: &'x str,
+}
+impl<'x> ChildTask<'x> {
+    /// The values of the hidden `--child` argument, one per field, so that
+    /// names can contain anything but the nul byte an argument can't.
+    fn to_args(&self) -> Result<[String; 4]> {
+        let args = [
+            self.allocator.to_string(),
+            self.percent.to_string(),
+            self.impl_name.to_string(),
+            self.scenario.to_string(),
+        ];
+        if let Some(x) = args.iter().find(|x| x.contains('\0')) {
+            bail!("can't pass `{}` to an isolated scenario", x.escape_debug());
+        }
+        Ok(args)
+    }
+
+    pub fn parse(args: &'x [String]) -> Result<ChildTask<'x>> {
+        let [allocator, percent, impl_name, scenario] = args else {
+            bail!("expected an allocator, percent, impl and scenario, got {args:?}");
+        };
+        Ok(ChildTask {
+            allocator,
+            percent: percent
+                .parse()
+                .with_context(|| format!("expected a percent, got `{percent}`"))?,
+            impl_name,
+            scenario,
+        })
+    }
+}
+
+/// Prints the result of a child so that [`run_child`] can pick it up.
+pub fn report(record: &ResultRecord) -> Result<()> {
+    println!("{}", result_line(record)?);
+    Ok(())
+}
+
+fn result_line(record: &ResultRecord) -> Result<String> {
+    Ok(format!("{RESULT_MARKER}{}", serde_json::to_string(record)?))
+}
+
+/// The result in the output of a child, if it printed one.
+fn parse_result(stdout: &str) -> Result<Option<ResultRecord>> {
+    stdout
+        .lines()
+        .find_map(|x| x.strip_prefix(RESULT_MARKER))
+        .map(serde_json::from_str::<ResultRecord>)
+        .transpose()
+        .context("couldn't parse the result of an isolated scenario")
+}
+
+/// How often a child with a timeout is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs `task` in a new instance of the runner, with the
+/// same arguments as this one. The child's stderr is passed through so that
+/// panic messages and failed checks stay visible. A child still running after
+/// `timeout` is killed.
+pub fn run_child(
+    task: &ChildTask,
+    timeout: Option<Duration>,
+) -> Result<(Status, Option<ResultRecord>)> {
+    let exe = env::current_exe().context("couldn't find the runner executable")?;
+    let mut child = Command::new(exe)
+        .args(env::args_os().skip(1))
+        .arg("--child")
+        .args(task.to_args()?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("couldn't start the runner for an isolated scenario")?;
+
+    // Drained on another thread so that a chatty child can't block on a full
+    // pipe while we wait for it.
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = thread::spawn(move || {
+        let mut data = Vec::new();
+        stdout.read_to_end(&mut data).map(|_| data)
+    });
+
+    let exit = match timeout {
+        None => Some(child.wait()?),
+        Some(timeout) => {
+            let start = Instant::now();
+            loop {
+                if let Some(exit) = child.try_wait()? {
+                    break Some(exit);
+                }
+                if start.elapsed() >= timeout {
+                    child.kill()?;
+                    child.wait()?;
+                    break None;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    };
+    let stdout = reader.join().unwrap()?;
+    let Some(exit) = exit else {
+        return Ok((Status::Timeout, None));
+    };
+
+    let record = parse_result(&String::from_utf8_lossy(&stdout))?;
+
+    let status = match (signal(exit), exit.code()) {
+        (Some(x), _) => Status::Signal(x),
+        (None, Some(0)) if record.is_some() => Status::Ok,
+        (None, code) => Status::Exit(code.unwrap_or(-1)),
+    };
+    Ok((status, record))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_metrics_survive_the_child() {
+        let metrics = [("mean", f64::NAN), ("rate", f64::INFINITY), ("sum", 3.5)];
+        let record = ResultRecord {
+            metrics: metrics.map(|(x, y)| (x.to_string(), y)).into(),
+            ..ResultRecord::default()
+        };
+        let stdout = format!("testing\n{}\n", result_line(&record).unwrap());
+        let metrics = parse_result(&stdout).unwrap().unwrap().metrics;
+        assert!(metrics["mean"].is_nan());
+        assert_eq!(metrics["rate"], f64::INFINITY);
+        assert_eq!(metrics["sum"], 3.5);
+
+        let old = r#"{"scenario": "sum", "impl_name": "rust_rc_impl", "allocator": "system",
+            "percent": 100, "samples": 1, "run_time": {"min_ns": 0, "median_ns": 0,
+            "mean_ns": 0, "stddev_ns": 0, "p95_ns": 0}, "alloc_time": {"min_ns": 0,
+            "median_ns": 0, "mean_ns": 0, "stddev_ns": 0, "p95_ns": 0}, "no_allocs": 0,
+            "max_memory": 0, "metrics": {"mean": null}}"#;
+        let record: ResultRecord = serde_json::from_str(old).unwrap();
+        assert!(record.metrics["mean"].is_nan());
+    }
+
+    #[test]
+    fn child_tasks_round_trip() {
+        let task = ChildTask {
+            allocator: "arena_reuse",
+            percent: 50,
+            impl_name: "lib/rust_*",
+            scenario: "-a/b,c",
+        };
+        let args = task.to_args().unwrap();
+        let parsed = ChildTask::parse(&args).unwrap();
+        assert_eq!(parsed.allocator, task.allocator);
+        assert_eq!(parsed.percent, task.percent);
+        assert_eq!(parsed.impl_name, task.impl_name);
+        assert_eq!(parsed.scenario, task.scenario);
+
+        let task = ChildTask {
+            scenario: "a\0b",
+            ..task
+        };
+        assert!(task.to_args().is_err());
+        assert!(ChildTask::parse(&args[1..]).is_err());
+        assert!(ChildTask::parse(&[
+            args[0].clone(),
+            "x".to_string(),
+            args[2].clone(),
+            args[3].clone()
+        ])
+        .is_err());
+    }
+}
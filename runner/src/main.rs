@@ -1,31 +1,67 @@
 #![feature(allocator_api)]
 
-use anyhow::Result;
+mod baseline;
+mod export;
+mod filter;
+mod isolate;
+mod manifest;
+mod params;
+mod sched;
+mod stats;
+mod svg;
+mod sweep;
+mod tables;
+
+use anyhow::{bail, Context, Result};
 use ascii_table::{Align, AsciiTable};
-use clap::{arg, Parser};
+use clap::{arg, Parser, Subcommand};
+use export::{OutputFormat, ResultRecord, ResultSet, RunMetadata};
+use filter::Filter;
 use humansize::{format_size, BINARY};
 use indexmap::IndexMap;
+use isolate::{ChildTask, Status};
 use libloading::{Library, Symbol};
+use manifest::{LibraryInfo, Manifest};
+use stats::{Counts, Metrics, Summary};
 use std::{
     alloc::{Allocator, Global},
-    array,
+    cell::RefCell,
+    collections::BTreeSet,
     fmt::Display,
-    mem::ManuallyDrop,
-    time::{Duration, Instant},
+    mem::{size_of, ManuallyDrop},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tests_api::{
-    arena_alloc::ArenaAlloc, snalloc::SnAlloc, stats_alloc::StatsAllocator, FnLoadTests,
-    FnScenarioNew, FnScenarioRun, RawLoadResult, RawScenarioInit, RawScenarioKind,
+    arena_alloc::ArenaAlloc,
+    guard_alloc::GuardAlloc,
+    metrics::RawMetrics,
+    params::{RawParam, RawParamValue},
+    raw_alloc::RawAllocator,
+    snalloc::SnAlloc,
+    stats_alloc::StatsAllocator,
+    validation::RawValidation,
+    FnApiVersion, FnLoadTests, FnScenarioDrop, FnScenarioNew, FnScenarioRun, RawLoadResult,
+    RawScenarioInit, RawScenarioKind, API_MAGIC, API_VERSION,
 };
 
 struct ScenarioData {
     name: &'static str,
+    kind: RawScenarioKind,
     new: FnScenarioNew,
     run: FnScenarioRun,
+    drop: FnScenarioDrop,
+    params: &'static [RawParam],
+    /// The value of every parameter; defaults unless overridden.
+    values: Vec<RawParamValue>,
 }
 
 struct TestData {
     name: String,
+    /// The name without the prefix of the library.
+    short_name: &'static str,
+    source: String,
     scenarios: Vec<ScenarioData>,
 }
 
@@ -37,225 +73,630 @@ unsafe fn s(ptr: *const u8, size: usize) -> &'static str {
 
 unsafe fn wrap_raw_tests(
     prefix: &str,
+    source: &str,
     raw_tests: RawLoadResult,
     tests: &mut Vec<TestData>,
-    is_bench: bool,
-    is_validation: bool,
-    specific_impl: Option<String>,
-    specific_scenario: Option<String>,
 ) {
-    let mut specific_impl_found = false;
-    let mut specific_scenario_found = false;
     for i in 0..raw_tests.list_impl_count {
         let current = &*raw_tests.list_impl.add(i);
 
-        let name = s(current.name, current.name_size);
-        let name = format!("{}_{}", prefix, name);
-
-        if let Some(n) = specific_impl.as_deref() {
-            if n != name {
-                continue;
-            }
-            specific_impl_found = true;
-        }
+        let short_name = s(current.name, current.name_size);
+        let name = format!("{}_{}", prefix, short_name);
 
         let mut scenarios = Vec::with_capacity(16);
         for i in 0..current.scenarios_count {
             let current = &*current.scenarios.add(i);
-
-            let add = match (&current.kind, is_bench, is_validation) {
-                (RawScenarioKind::Bench, true, _) | (RawScenarioKind::Validation, _, true) => true,
-                _ => false,
+            let params: &[RawParam] = match current.params_count {
+                0 => &[],
+                count => std::slice::from_raw_parts(current.params, count),
             };
-            if !add {
-                continue;
-            }
-
-            let name = s(current.name, current.name_size);
-
-            if let Some(n) = specific_scenario.as_deref() {
-                if n != name {
-                    continue;
-                }
-                specific_scenario_found = true;
-            }
 
             scenarios.push(ScenarioData {
-                name,
+                name: s(current.name, current.name_size),
+                kind: current.kind,
                 new: current.new,
                 run: current.run,
+                drop: current.drop,
+                params,
+                values: params.iter().map(|x| x.default_value).collect(),
             });
         }
 
-        tests.push(TestData { name, scenarios });
+        tests.push(TestData {
+            name,
+            short_name,
+            source: source.to_string(),
+            scenarios,
+        });
     }
+}
 
-    match specific_impl {
-        Some(x) if !specific_impl_found => {
-            panic!("no impl with the name `{}` was found", x);
-        }
-        _ => {}
+/// Keeps the impls and scenarios selected by the filters and, if given, by
+/// the kinds of scenarios to run. Impls left without scenarios are dropped.
+fn select(
+    tests: Vec<TestData>,
+    kinds: Option<(bool, bool)>,
+    impls: &Filter,
+    scenarios: &Filter,
+) -> Result<Vec<TestData>> {
+    let impl_names: Vec<&str> = tests
+        .iter()
+        .flat_map(|x| [x.name.as_str(), x.short_name])
+        .collect();
+    if let Some(x) = impls.unmatched(&impl_names) {
+        bail!("no impl matches `{x}`");
+    }
+    let scenario_names: Vec<&str> = tests
+        .iter()
+        .flat_map(|x| x.scenarios.iter().map(|x| x.name))
+        .collect();
+    if let Some(x) = scenarios.unmatched(&scenario_names) {
+        bail!("no scenario matches `{x}`");
     }
-    match specific_scenario {
-        Some(x) if !specific_scenario_found => {
-            panic!("no scenario with the name `{}` was found", x);
+
+    let mut selected = Vec::with_capacity(tests.len());
+    for mut test in tests {
+        if !impls.matches(&[&test.name, test.short_name]) {
+            continue;
+        }
+        test.scenarios.retain(|x| {
+            let kind = match (x.kind, kinds) {
+                (_, None) => true,
+                (RawScenarioKind::Bench, Some((is_bench, _))) => is_bench,
+                (RawScenarioKind::Validation, Some((_, is_validation))) => is_validation,
+            };
+            kind && scenarios.matches(&[x.name])
+        });
+        if !test.scenarios.is_empty() {
+            selected.push(test);
         }
-        _ => {}
     }
+
+    if selected.is_empty() {
+        bail!("the filters and kinds don't leave anything to run");
+    }
+    Ok(selected)
+}
+
+/// Keeps the one scenario of one impl a `--child` runs. The names are exact,
+/// not filters, as they may contain `,`, `!`, `*` or `?`.
+fn select_child(tests: Vec<TestData>, task: &ChildTask) -> Result<Vec<TestData>> {
+    let Some(mut test) = tests.into_iter().find(|x| x.name == task.impl_name) else {
+        bail!("no impl `{}`", task.impl_name);
+    };
+    test.scenarios.retain(|x| x.name == task.scenario);
+    if test.scenarios.is_empty() {
+        bail!("no scenario `{}` in `{}`", task.scenario, task.impl_name);
+    }
+    Ok(vec![test])
 }
 
 unsafe fn load(
     prefix: &str,
-    path: &str,
+    source: &str,
+    load_tests: FnLoadTests,
     tests: &mut Vec<TestData>,
-    is_bench: bool,
-    is_validation: bool,
-    specific_impl: Option<String>,
-    specific_scenario: Option<String>,
 ) -> Result<()> {
-    println!("loading {path}");
+    let raw_tests = load_tests();
+    if raw_tests.magic != API_MAGIC {
+        bail!(
+            "`{source}` returned garbage from `load_tests` (magic {:#x}, expected {API_MAGIC:#x})",
+            raw_tests.magic
+        );
+    }
+    if raw_tests.version != API_VERSION || raw_tests.size != size_of::<RawLoadResult>() {
+        bail!(
+            "`{source}` was built against tests_api version {} ({} byte RawLoadResult), \
+             the runner expects version {API_VERSION} ({} bytes)",
+            raw_tests.version,
+            raw_tests.size,
+            size_of::<RawLoadResult>()
+        );
+    }
 
-    let load_tests = if false {
-        let lib = ManuallyDrop::new(Library::new(path)?);
-        let _load_tests: Symbol<FnLoadTests> = lib.get(b"load_tests\0")?;
-        todo!()
-    } else {
-        rust_tests::load_tests
-    };
+    wrap_raw_tests(prefix, source, raw_tests, tests);
+    Ok(())
+}
 
-    let raw_tests = load_tests();
-    wrap_raw_tests(
-        prefix,
-        raw_tests,
-        tests,
-        is_bench,
-        is_validation,
-        specific_impl,
-        specific_scenario,
+fn kind_name(kind: RawScenarioKind) -> &'static str {
+    match kind {
+        RawScenarioKind::Bench => "bench",
+        RawScenarioKind::Validation => "validation",
+    }
+}
+
+/// Prints the impls and their scenarios for `runner list`.
+fn list(tests: &[TestData]) {
+    for test in tests {
+        println!("{} ({})", test.name, test.source);
+        for i in test.scenarios.iter() {
+            let line = format!(
+                "    {:<32} {:<10} {}",
+                i.name,
+                kind_name(i.kind),
+                params::describe(i)
+            );
+            println!("{}", line.trim_end());
+        }
+    }
+}
+
+unsafe fn open_library(path: &Path) -> Result<FnLoadTests> {
+    println!("loading {}", path.display());
+
+    // The names and scenarios handed out by the library point into it, so it
+    // is never unloaded.
+    let lib = ManuallyDrop::new(
+        Library::new(path)
+            .with_context(|| format!("couldn't load library `{}`", path.display()))?,
     );
 
-    Ok(())
+    let api_version: Symbol<FnApiVersion> = lib.get(b"api_version\0").with_context(|| {
+        format!(
+            "`{}` doesn't export `api_version`; it was built against a tests_api older \
+             than the runner's version {API_VERSION}",
+            path.display()
+        )
+    })?;
+    let version = api_version();
+    if version != API_VERSION {
+        bail!(
+            "`{}` was built against tests_api version {version}, the runner expects version \
+             {API_VERSION}",
+            path.display()
+        );
+    }
+
+    let load_tests: Symbol<FnLoadTests> = lib
+        .get(b"load_tests\0")
+        .with_context(|| format!("`{}` doesn't export `load_tests`", path.display()))?;
+
+    Ok(*load_tests)
+}
+
+/// The C++ solutions are built by `cpp_tests_build` next to the runner.
+fn cpp_library() -> Option<PathBuf> {
+    let (dl_prefix, dl_suffix) = DL_AFFIXES;
+    let exe = std::env::current_exe().ok()?;
+    let path = exe.with_file_name(format!("{dl_prefix}cpp_tests{dl_suffix}"));
+    path.exists().then_some(path)
+}
+
+/// Splits a `--lib` argument into the library path and the prefix of its
+/// impls. Without an explicit prefix, `libfoo_tests.so` gets `foo`.
+fn parse_lib(arg: &str) -> (&str, String) {
+    match arg.rsplit_once(':') {
+        Some((path, prefix)) if !prefix.is_empty() && !prefix.contains(['/', '\\']) => {
+            (path, prefix.to_string())
+        }
+        _ => {
+            let (dl_prefix, dl_suffix) = DL_AFFIXES;
+            let file_name = Path::new(arg)
+                .file_name()
+                .and_then(|x| x.to_str())
+                .unwrap_or(arg);
+            let stem = file_name.strip_suffix(dl_suffix).unwrap_or(file_name);
+            let stem = stem.strip_prefix(dl_prefix).unwrap_or(stem);
+            let stem = stem.strip_suffix("_tests").unwrap_or(stem);
+            (arg, stem.to_string())
+        }
+    }
 }
 
 #[derive(Default)]
 struct TestResultExtra {
+    setup_time: String,
     run_time: String,
+    run_spread: String,
+    run_mean: String,
+    teardown_time: String,
     alloc_time: String,
+    alloc_spread: String,
+    alloc_mean: String,
     slower_run: String,
+    bytes_copied: String,
     max_memory: String,
+    metrics: Vec<String>,
 }
 
 struct TestResult<'x> {
     scenario: &'x str,
     impl_name: &'x str,
-    run_time: Duration,
-    alloc_time: Duration,
+    allocator: &'static str,
+    percent: u32,
+    samples: usize,
+    status: Status,
+    setup_time: Summary,
+    run_time: Summary,
+    teardown_time: Summary,
+    alloc_time: Summary,
     no_allocs: usize,
+    no_reallocs: usize,
+    bytes_copied: usize,
     max_memory: usize,
+    metrics: Metrics,
     extra: TestResultExtra,
 }
 
-fn bench<'x>(
-    test: &'x TestData,
-    results: &mut IndexMap<&str, Vec<TestResult<'x>>>,
+/// Results grouped by scenario, allocator and percent; `slower(run)` is
+/// relative to the fastest impl of a group.
+type Results<'x> = IndexMap<(&'x str, &'static str, u32), Vec<TestResult<'x>>>;
+
+#[derive(Clone, Copy)]
+struct BenchOptions {
     allocator_kind: AllocatorKind,
     percent: u32,
     is_bench: bool,
-) {
-    println!("testing {}", test.name);
+    repeat: usize,
+    warmup: usize,
+    isolate: bool,
+    timeout: Option<Duration>,
+    seed: u64,
+}
+
+struct Sample {
+    setup_time: Duration,
+    run_time: Duration,
+    teardown_time: Duration,
+    alloc_time: Duration,
+    counts: Counts,
+    metrics: Metrics,
+}
+
+fn run_once(scenario: &ScenarioData, options: BenchOptions) -> Sample {
+    let is_validation = scenario.kind == RawScenarioKind::Validation;
+    let alloc = options
+        .allocator_kind
+        .create(options.is_bench, is_validation);
+    let alloc: &'static dyn TestAllocator = unsafe {
+        // TODO: this is here to transmute the lifetime to static.
+        // This is not great and should fixed at some point.
+        std::mem::transmute(&*alloc)
+    };
+    let is_freed = |ptr: *const u8, size: usize| alloc.is_freed(ptr, size);
+    let raw_validation = RawValidation::new(&is_freed);
+    let alloc = StatsAllocator::new(alloc as &dyn Allocator);
+
+    let raw_alloc = RawAllocator::new(&alloc);
+    let metrics = RefCell::new(Metrics::new());
+    let report = |name: &str, value: f64| {
+        metrics.borrow_mut().insert(name.to_string(), value);
+    };
+    let raw_metrics = RawMetrics::new(&report);
+    let init = RawScenarioInit {
+        alloc: &raw_alloc,
+        percent: options.percent,
+        metrics: &raw_metrics,
+        params: scenario.values.as_ptr(),
+        seed: options.seed,
+        validation: &raw_validation,
+    };
+    let time = Instant::now();
+    let object = unsafe { (scenario.new)(init) };
+    let setup_time = time.elapsed();
+    alloc.reset_time();
+    let time = Instant::now();
+    unsafe { (scenario.run)(object) };
+    let elapsed = time.elapsed();
+    let alloc_time = alloc.time();
+    let time = Instant::now();
+    unsafe { (scenario.drop)(object) };
+    let teardown_time = time.elapsed();
+
+    Sample {
+        setup_time,
+        run_time: elapsed - alloc_time,
+        teardown_time,
+        alloc_time,
+        counts: Counts {
+            no_allocs: alloc.no_allocs(),
+            no_reallocs: alloc.no_reallocs(),
+            bytes_copied: alloc.bytes_copied(),
+            max_memory: alloc.max_allocated(),
+        },
+        metrics: metrics.take(),
+    }
+}
+
+fn measure<'x>(
+    test: &'x TestData,
+    scenario: &'x ScenarioData,
+    options: BenchOptions,
+) -> TestResult<'x> {
+    for _ in 0..options.warmup {
+        run_once(scenario, options);
+    }
+
+    let mut setup_times = Vec::with_capacity(options.repeat);
+    let mut run_times = Vec::with_capacity(options.repeat);
+    let mut teardown_times = Vec::with_capacity(options.repeat);
+    let mut alloc_times = Vec::with_capacity(options.repeat);
+    let mut counts = Counts::default();
+    let mut metrics = Vec::with_capacity(options.repeat);
+    for _ in 0..options.repeat {
+        let sample = run_once(scenario, options);
+        setup_times.push(sample.setup_time);
+        run_times.push(sample.run_time);
+        teardown_times.push(sample.teardown_time);
+        alloc_times.push(sample.alloc_time);
+        counts = counts.max(sample.counts);
+        metrics.push(sample.metrics);
+    }
+
+    TestResult {
+        scenario: scenario.name,
+        impl_name: &test.name,
+        allocator: options.allocator_kind.name(),
+        percent: options.percent,
+        samples: options.repeat,
+        status: Status::Ok,
+        setup_time: Summary::new(&setup_times),
+        run_time: Summary::new(&run_times),
+        teardown_time: Summary::new(&teardown_times),
+        alloc_time: Summary::new(&alloc_times),
+        no_allocs: counts.no_allocs,
+        no_reallocs: counts.no_reallocs,
+        bytes_copied: counts.bytes_copied,
+        max_memory: counts.max_memory,
+        metrics: stats::mean_metrics(&metrics),
+        extra: TestResultExtra::default(),
+    }
+}
+
+/// Like [`measure`], but in a child process; a crash becomes a failed result.
+fn measure_isolated<'x>(
+    test: &'x TestData,
+    scenario: &'x ScenarioData,
+    options: BenchOptions,
+) -> Result<TestResult<'x>> {
+    let (status, record) = isolate::run_child(
+        &ChildTask {
+            allocator: options.allocator_kind.name(),
+            percent: options.percent,
+            impl_name: &test.name,
+            scenario: scenario.name,
+        },
+        options.timeout,
+    )?;
+    if !status.is_ok() {
+        println!("        {status}");
+    }
+
+    let record = record.filter(|_| status.is_ok());
+    Ok(TestResult {
+        scenario: scenario.name,
+        impl_name: &test.name,
+        allocator: options.allocator_kind.name(),
+        percent: options.percent,
+        samples: record.as_ref().map_or(0, |x| x.samples),
+        status,
+        setup_time: record
+            .as_ref()
+            .map(|x| (&x.setup_time).into())
+            .unwrap_or_default(),
+        run_time: record
+            .as_ref()
+            .map(|x| (&x.run_time).into())
+            .unwrap_or_default(),
+        teardown_time: record
+            .as_ref()
+            .map(|x| (&x.teardown_time).into())
+            .unwrap_or_default(),
+        alloc_time: record
+            .as_ref()
+            .map(|x| (&x.alloc_time).into())
+            .unwrap_or_default(),
+        no_allocs: record.as_ref().map_or(0, |x| x.no_allocs),
+        no_reallocs: record.as_ref().map_or(0, |x| x.no_reallocs),
+        bytes_copied: record.as_ref().map_or(0, |x| x.bytes_copied),
+        max_memory: record.as_ref().map_or(0, |x| x.max_memory),
+        metrics: record.map(|x| x.metrics).unwrap_or_default(),
+        extra: TestResultExtra::default(),
+    })
+}
+
+fn bench<'x>(test: &'x TestData, results: &mut Results<'x>, options: BenchOptions) -> Result<()> {
+    println!(
+        "testing {} with {} at {}%",
+        test.name,
+        options.allocator_kind.name(),
+        options.percent
+    );
 
     for i in test.scenarios.iter() {
         println!("    scenario {}", i.name);
-        let alloc = allocator_kind.create(is_bench);
-        let alloc: &'static dyn Allocator = unsafe {
-            // TODO: this is here to transmute the lifetime to static.
-            // This is not great and should fixed at some point.
-            std::mem::transmute(&*alloc)
-        };
-        let alloc = StatsAllocator::new(alloc);
 
-        let alloc_ptr: *const dyn Allocator = &alloc;
-        let alloc_ptr = &alloc_ptr;
-        let init = RawScenarioInit {
-            alloc: alloc_ptr,
-            percent,
+        let result = if options.isolate {
+            measure_isolated(test, i, options)?
+        } else {
+            measure(test, i, options)
         };
-        let object = unsafe { (i.new)(init) };
-        alloc.reset_time();
-        let time = Instant::now();
-        unsafe { (i.run)(object) };
-        let elapsed = time.elapsed();
-        let alloc_time = alloc.time();
-        results
-            .entry(i.name)
-            .or_insert(Vec::new())
-            .push(TestResult {
-                scenario: i.name,
-                impl_name: &test.name,
-                run_time: elapsed - alloc_time,
-                alloc_time,
-                no_allocs: alloc.no_allocs(),
-                max_memory: alloc.max_allocated(),
-                extra: TestResultExtra::default(),
-            });
+        let key = (i.name, options.allocator_kind.name(), options.percent);
+        results.entry(key).or_insert(Vec::new()).push(result);
     }
+
+    Ok(())
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print every impl and its scenarios without running anything
+    List,
 }
 
 #[derive(Parser)]
 struct Args {
-    // Allocators: default, system, arena, sn
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Comma separated allocators to run every scenario with: default,
+    /// system, arena, arena_reuse, sn, guard (validation only)
     #[arg(short, long, default_value = "default")]
     allocator: String,
     /// Percent of number of iterations of tests
     #[arg(short, long, default_value_t = 100)]
     percent: u32,
+    /// Run every scenario at each of these percents instead, as a comma
+    /// separated list that can contain `start..end:step` ranges
+    #[arg(long)]
+    sweep: Option<String>,
 
     /// Enable bench tests
     #[arg(short, long, default_value = "bench")]
     kinds: String,
 
-    /// Run only a specific impl
+    /// Number of measured runs of every scenario
+    #[arg(short, long, default_value_t = 1)]
+    repeat: usize,
+    /// Number of unmeasured runs of every scenario before the measured ones
+    #[arg(short, long, default_value_t = 0)]
+    warmup: usize,
+    /// Run every scenario in its own process, so that crashes are reported
+    /// instead of ending the run; always on for validation
+    #[arg(long)]
+    isolate: bool,
+    /// Kill scenarios running longer than this many seconds and report them
+    /// as timed out; implies --isolate
     #[arg(short, long)]
+    timeout: Option<f64>,
+    /// Used by --isolate: run only this allocator, percent, impl and scenario
+    /// and print the result
+    #[arg(long, hide = true, num_args = 4, allow_hyphen_values = true)]
+    child: Option<Vec<String>>,
+
+    /// Seed of the randomness of the scenarios; the same seed gives every impl
+    /// the same work
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Override a parameter of a scenario in every impl, as
+    /// `scenario.name=value`; can be repeated
+    #[arg(long = "param", global = true)]
+    params: Vec<String>,
+
+    /// Pin the runner to this cpu
+    #[arg(long)]
+    pin_cpu: Option<usize>,
+    /// Run at this nice value; negative values need privileges
+    #[arg(long, allow_hyphen_values = true)]
+    nice: Option<i32>,
+    /// Run with SCHED_FIFO at this priority (1 to 99); needs privileges
+    #[arg(long)]
+    realtime: Option<i32>,
+
+    /// Impls to run, as comma separated names or globs; `!` excludes
+    #[arg(short, long, global = true)]
     impl_name: Option<String>,
-    /// Run only a specific scenario
-    #[arg(short, long)]
+    /// Scenarios to run, as comma separated names or globs; `!` excludes
+    #[arg(short, long, global = true)]
     scenario: Option<String>,
+
+    /// Also load the impls of a shared library, as `path[:prefix]`
+    #[arg(short, long = "lib", global = true)]
+    libs: Vec<String>,
+    /// Don't load the C++ impls
+    #[arg(long, global = true)]
+    no_cpp: bool,
+
+    /// Write the results to this file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Format of the output file; guessed from the extension if missing
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Split LaTeX and Markdown output into one table per scenario
+    #[arg(long)]
+    group_by_scenario: bool,
+    /// Write SVG charts of the results to this directory
+    #[arg(long)]
+    charts: Option<PathBuf>,
+
+    /// Save the results as a baseline with this name
+    #[arg(long)]
+    save_baseline: Option<String>,
+    /// Compare the results against the baseline with this name
+    #[arg(long)]
+    compare: Option<String>,
+    /// Directory where baselines are stored
+    #[arg(long, default_value = "target/baselines")]
+    baseline_dir: PathBuf,
+    /// Slowdown in percent above which a comparison counts as a regression
+    #[arg(long, default_value_t = 5.0)]
+    threshold: f64,
 }
 
-const DL_NAMES: (&str, &str) = if cfg!(target_os = "windows") {
-    ("rust_tests.dll", "cpp_tests.dll")
+const DL_AFFIXES: (&str, &str) = if cfg!(target_os = "windows") {
+    ("", ".dll")
 } else if cfg!(target_os = "linux") || cfg!(target_os = "android") {
-    ("librust_tests.so", "libcpp_tests.so")
+    ("lib", ".so")
 } else if cfg!(target_os = "macos") {
-    ("librust_tests.dylib", "libcpp_tests.dylib")
+    ("lib", ".dylib")
 } else {
     panic!("what are you running on? 🤔");
 };
 
+/// Freed memory the arenas keep from being reused in validation scenarios.
+const QUARANTINE: usize = 1024 * 1024;
+
+/// An allocator that validation scenarios can ask whether memory is freed.
+trait TestAllocator: Allocator {
+    fn is_freed(&self, _ptr: *const u8, _size: usize) -> bool {
+        false
+    }
+}
+impl TestAllocator for Global {}
+impl TestAllocator for SnAlloc {}
+impl TestAllocator for GuardAlloc {}
+impl TestAllocator for ArenaAlloc {
+    fn is_freed(&self, ptr: *const u8, size: usize) -> bool {
+        ArenaAlloc::is_freed(self, ptr, size)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum AllocatorKind {
     System,
     Arena,
+    ArenaReuse,
     Sn,
+    Guard,
 }
 impl AllocatorKind {
-    fn create(self, is_bench: bool) -> Box<dyn Allocator> {
+    /// Arenas poison freed memory for validation scenarios.
+    fn create(self, is_bench: bool, is_validation: bool) -> Box<dyn TestAllocator> {
         let size = if is_bench {
             2 * 1024 * 1024 * 1024
         } else {
             4096
         };
+        let arena = |arena: ArenaAlloc| -> Box<dyn TestAllocator> {
+            if is_validation {
+                Box::new(arena.with_poisoning(QUARANTINE))
+            } else {
+                Box::new(arena)
+            }
+        };
         match self {
             AllocatorKind::System => Box::new(Global),
-            AllocatorKind::Arena => Box::new(ArenaAlloc::new(size)),
+            AllocatorKind::Arena => arena(ArenaAlloc::new(size)),
+            AllocatorKind::ArenaReuse => arena(ArenaAlloc::with_reuse(size)),
             AllocatorKind::Sn => Box::new(SnAlloc::new()),
+            AllocatorKind::Guard => Box::new(GuardAlloc::new()),
         }
     }
+    /// Whether validation scenarios can tell bugs apart with this allocator.
+    fn can_validate(self) -> bool {
+        matches!(
+            self,
+            AllocatorKind::Arena | AllocatorKind::ArenaReuse | AllocatorKind::Guard
+        )
+    }
     fn name(self) -> &'static str {
         match self {
             AllocatorKind::System => "system",
             AllocatorKind::Arena => "arena",
+            AllocatorKind::ArenaReuse => "arena_reuse",
             AllocatorKind::Sn => "sn",
+            AllocatorKind::Guard => "guard",
         }
     }
     fn parse(name: &str, default: AllocatorKind) -> AllocatorKind {
@@ -263,13 +704,25 @@ impl AllocatorKind {
             "default" => default,
             "system" => AllocatorKind::System,
             "arena" => AllocatorKind::Arena,
+            "arena_reuse" => AllocatorKind::ArenaReuse,
             "sn" => AllocatorKind::Sn,
+            "guard" => AllocatorKind::Guard,
             _ => panic!("unknown allocator: {name}"),
         }
     }
+    fn parse_list(names: &str, default: AllocatorKind) -> Vec<AllocatorKind> {
+        let mut kinds = Vec::new();
+        for i in names.split(',') {
+            let kind = AllocatorKind::parse(i.trim(), default);
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        kinds
+    }
 }
 
-fn parse_scenarios(s: String) -> (bool, bool) {
+fn parse_scenarios(s: &str) -> (bool, bool) {
     let mut is_bench = false;
     let mut is_validation = false;
 
@@ -284,67 +737,320 @@ fn parse_scenarios(s: String) -> (bool, bool) {
     (is_bench, is_validation)
 }
 
-fn create_table() -> AsciiTable {
+/// The fixed columns followed by one column per metric name.
+fn create_table(metric_names: &[&str]) -> AsciiTable {
     let mut ascii_table = AsciiTable::default();
     ascii_table.set_max_width(200);
 
     let columns = [
         ("scenario", Align::Center),
         ("name", Align::Center),
+        ("allocator", Align::Center),
+        ("percent", Align::Right),
+        ("status", Align::Center),
+        ("setup", Align::Right),
         ("time", Align::Right),
+        ("min / p95", Align::Right),
+        ("mean ± σ", Align::Right),
+        ("teardown", Align::Right),
         ("alloc_time", Align::Right),
+        ("alloc min / p95", Align::Right),
+        ("alloc mean ± σ", Align::Right),
         ("slower(run)", Align::Right),
         ("no. allocs", Align::Right),
+        ("reallocs", Align::Right),
+        ("copied", Align::Right),
         ("max memory", Align::Right),
     ];
 
-    for (index, (name, alignment)) in columns.iter().enumerate() {
+    let metric_columns = metric_names.iter().map(|x| (*x, Align::Right));
+    for (index, (name, alignment)) in columns.into_iter().chain(metric_columns).enumerate() {
         ascii_table
             .column(index)
-            .set_header(*name)
-            .set_align(*alignment);
+            .set_header(name)
+            .set_align(alignment);
     }
 
     ascii_table
 }
 
-fn main_impl() -> Result<()> {
+fn print_results(results: &mut Results) {
+    let metric_names: BTreeSet<String> = results
+        .values()
+        .flatten()
+        .flat_map(|x| x.metrics.keys().cloned())
+        .collect();
+    let metric_names: Vec<&str> = metric_names.iter().map(|x| x.as_str()).collect();
+
+    let mut output: Vec<Vec<&dyn Display>> = Vec::with_capacity(64);
+    for tests in results.values_mut() {
+        let min_run = tests
+            .iter()
+            .filter(|x| x.status.is_ok())
+            .map(|x| x.run_time.median)
+            .min()
+            .unwrap_or_default();
+        tests.sort_by_key(|x| (!x.status.is_ok(), x.run_time.median));
+        for i in tests {
+            if !i.status.is_ok() {
+                let dash = || "-".to_string();
+                i.extra = TestResultExtra {
+                    setup_time: dash(),
+                    run_time: dash(),
+                    run_spread: dash(),
+                    run_mean: dash(),
+                    teardown_time: dash(),
+                    alloc_time: dash(),
+                    alloc_spread: dash(),
+                    alloc_mean: dash(),
+                    slower_run: dash(),
+                    bytes_copied: dash(),
+                    max_memory: dash(),
+                    metrics: metric_names.iter().map(|_| dash()).collect(),
+                };
+            } else {
+                i.extra = TestResultExtra {
+                    setup_time: format!("{:.2?}", i.setup_time.median),
+                    run_time: format!("{:?}", i.run_time.median),
+                    run_spread: format!("{:.2?} / {:.2?}", i.run_time.min, i.run_time.p95),
+                    run_mean: format!("{:.2?} ± {:.2?}", i.run_time.mean, i.run_time.stddev),
+                    teardown_time: format!("{:.2?}", i.teardown_time.median),
+                    alloc_time: format!("{:?}", i.alloc_time.median),
+                    alloc_spread: format!("{:.2?} / {:.2?}", i.alloc_time.min, i.alloc_time.p95),
+                    alloc_mean: format!("{:.2?} ± {:.2?}", i.alloc_time.mean, i.alloc_time.stddev),
+                    slower_run: format!(
+                        "{:.02}x",
+                        i.run_time.median.as_secs_f64() / min_run.as_secs_f64()
+                    ),
+                    bytes_copied: format_size(i.bytes_copied, BINARY),
+                    max_memory: format_size(i.max_memory, BINARY),
+                    metrics: metric_names
+                        .iter()
+                        .map(|x| match i.metrics.get(*x) {
+                            Some(value) => stats::format_metric(*value),
+                            None => String::new(),
+                        })
+                        .collect(),
+                };
+            }
+
+            let mut row: Vec<&dyn Display> = vec![
+                &i.scenario,
+                &i.impl_name,
+                &i.allocator,
+                &i.percent,
+                &i.status,
+                &i.extra.setup_time,
+                &i.extra.run_time,
+                &i.extra.run_spread,
+                &i.extra.run_mean,
+                &i.extra.teardown_time,
+                &i.extra.alloc_time,
+                &i.extra.alloc_spread,
+                &i.extra.alloc_mean,
+                &i.extra.slower_run,
+                &i.no_allocs,
+                &i.no_reallocs,
+                &i.extra.bytes_copied,
+                &i.extra.max_memory,
+            ];
+            row.extend(i.extra.metrics.iter().map(|x| x as &dyn Display));
+            output.push(row);
+        }
+        let dashes = &"------";
+        output.push(vec![dashes as &dyn Display; 18 + metric_names.len()]);
+    }
+
+    create_table(&metric_names).print(output.iter());
+}
+
+/// One row per scenario and impl with the median run time under every
+/// allocator, relative to the fastest allocator of the row.
+fn print_allocator_view(results: &Results, allocators: &[&'static str]) {
+    let mut rows: IndexMap<(&str, &str, u32), Vec<Option<Duration>>> = IndexMap::new();
+    for ((scenario, allocator, percent), tests) in results.iter() {
+        let column = allocators.iter().position(|x| x == allocator).unwrap();
+        for i in tests {
+            let row = rows
+                .entry((scenario, i.impl_name, *percent))
+                .or_insert_with(|| vec![None; allocators.len()]);
+            row[column] = Some(i.run_time.median).filter(|_| i.status.is_ok());
+        }
+    }
+
+    let mut ascii_table = AsciiTable::default();
+    ascii_table.set_max_width(200);
+    let headers = ["scenario", "name", "percent"]
+        .into_iter()
+        .chain(allocators.iter().copied())
+        .chain(["best"]);
+    for (index, name) in headers.enumerate() {
+        let align = if index < 2 {
+            Align::Center
+        } else {
+            Align::Right
+        };
+        ascii_table.column(index).set_header(name).set_align(align);
+    }
+
+    let mut output: Vec<Vec<String>> = Vec::with_capacity(rows.len());
+    for ((scenario, impl_name, percent), times) in rows {
+        let best = times
+            .iter()
+            .enumerate()
+            .filter_map(|(index, x)| Some((index, (*x)?)))
+            .min_by_key(|(_, x)| *x);
+
+        let mut row = vec![
+            scenario.to_string(),
+            impl_name.to_string(),
+            percent.to_string(),
+        ];
+        for i in times.iter() {
+            row.push(match (i, best) {
+                (Some(x), Some((_, best))) => {
+                    format!("{x:.2?} ({:.02}x)", x.as_secs_f64() / best.as_secs_f64())
+                }
+                _ => "-".to_string(),
+            });
+        }
+        row.push(best.map_or("-", |(index, _)| allocators[index]).to_string());
+        output.push(row);
+    }
+
+    println!("\nmedian run time by allocator:");
+    ascii_table.print(output.iter());
+}
+
+fn load_all(args: &Args) -> Result<(Vec<TestData>, Vec<LibraryInfo>)> {
+    let mut tests = Vec::with_capacity(16);
+    unsafe {
+        let mut libs = vec![(
+            "rust".to_string(),
+            "built-in rust_tests".to_string(),
+            rust_tests::load_tests as FnLoadTests,
+        )];
+        if !args.no_cpp {
+            match cpp_library() {
+                Some(path) => libs.push((
+                    "cpp".to_string(),
+                    path.display().to_string(),
+                    open_library(&path)?,
+                )),
+                None => println!("C++ impls not found next to the runner; skipping them"),
+            }
+        }
+        for i in args.libs.iter() {
+            let (path, prefix) = parse_lib(i);
+            if libs.iter().any(|(x, _, _)| *x == prefix) {
+                bail!("prefix `{prefix}` of `{path}` is already used; pass `{path}:<prefix>`");
+            }
+            libs.push((prefix, path.to_string(), open_library(Path::new(path))?));
+        }
+
+        let mut infos = Vec::with_capacity(libs.len());
+        for (prefix, source, load_tests) in libs {
+            load(&prefix, &source, load_tests, &mut tests)?;
+            infos.push(LibraryInfo { prefix, source });
+        }
+        println!();
+        Ok((tests, infos))
+    }
+}
+
+fn main_impl() -> Result<ExitCode> {
     let args = Args::parse();
-    if !(1..=100).contains(&args.percent) {
+    let overrides = args
+        .params
+        .iter()
+        .map(|x| params::Override::parse(x))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(Command::List) = args.command {
+        let (mut tests, _) = load_all(&args)?;
+        params::apply(&mut tests, &overrides)?;
+        let tests = select(
+            tests,
+            None,
+            &Filter::parse(args.impl_name.as_deref()),
+            &Filter::parse(args.scenario.as_deref()),
+        )?;
+        list(&tests);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut percents = match args.sweep.as_deref() {
+        Some(x) => sweep::parse(x),
+        None => vec![args.percent],
+    };
+    if percents.iter().any(|x| !(1..=100).contains(x)) {
         panic!("percent expected to between 1..=100");
     }
-    let (is_bench, is_validation) = parse_scenarios(args.kinds);
+    if args.repeat == 0 {
+        panic!("repeat expected to be at least 1");
+    }
+    let (is_bench, is_validation) = parse_scenarios(&args.kinds);
     let default_allocator = if is_validation {
         AllocatorKind::Arena
     } else {
         AllocatorKind::System
     };
-    let allocator_kind = AllocatorKind::parse(&args.allocator, default_allocator);
-    if is_validation && allocator_kind != AllocatorKind::Arena {
-        panic!("validation must be run with arena allocator");
+    let mut allocators = AllocatorKind::parse_list(&args.allocator, default_allocator);
+    if is_validation && allocators.iter().any(|x| !x.can_validate()) {
+        panic!("validation must be run with an arena or the guard allocator");
+    }
+    if is_bench && allocators.contains(&AllocatorKind::Guard) {
+        panic!("the guard allocator is too slow for bench scenarios");
+    }
+    // Validation scenarios are expected to crash on some impls.
+    let isolate = (args.isolate || args.timeout.is_some() || is_validation) && args.child.is_none();
+    let timeout = args.timeout.map(Duration::from_secs_f64);
+    let child = args.child.as_deref().map(ChildTask::parse).transpose()?;
+    if let Some(task) = &child {
+        allocators = vec![AllocatorKind::parse(task.allocator, default_allocator)];
+        percents = vec![task.percent];
+    }
+    // Children inherit all of this from the parent.
+    if args.child.is_none() {
+        if let Some(cpu) = args.pin_cpu {
+            sched::pin_cpu(cpu)?;
+        }
+        if let Some(nice) = args.nice {
+            sched::set_nice(nice)?;
+        }
+        if let Some(priority) = args.realtime {
+            sched::set_realtime(priority)?;
+        }
+        sched::check_governor(args.pin_cpu);
     }
+    let allocator_names: Vec<&'static str> = allocators.iter().map(|x| x.name()).collect();
     println!(
-        "allocator: {}\npercent: {}\nbench: {}\nvalidation: {}",
-        allocator_kind.name(),
-        args.percent,
+        "allocators: {}\npercents: {}\nbench: {}\nvalidation: {}\nrepeat: {}\nwarmup: {}\nisolate: {}\nseed: {}",
+        allocator_names.join(", "),
+        percents
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
         is_bench,
-        is_validation
+        is_validation,
+        args.repeat,
+        args.warmup,
+        isolate,
+        args.seed
     );
 
-    let mut tests = Vec::with_capacity(16);
-    unsafe {
-        let (rust_path, _cpp_path) = DL_NAMES;
-        load(
-            "rust",
-            rust_path,
-            &mut tests,
-            is_bench,
-            is_validation,
-            args.impl_name,
-            args.scenario,
-        )?;
-        // load("cpp", cpp_path, &mut tests)?;
-        println!();
+    let (mut tests, libraries) = load_all(&args)?;
+    params::apply(&mut tests, &overrides)?;
+    let manifest = Manifest::new(libraries);
+    manifest.print();
+    println!();
+    let tests = match &child {
+        Some(task) => select_child(tests, task)?,
+        None => select(
+            tests,
+            Some((is_bench, is_validation)),
+            &Filter::parse(args.impl_name.as_deref()),
+            &Filter::parse(args.scenario.as_deref()),
+        )?,
     };
 
     println!(
@@ -353,47 +1059,103 @@ fn main_impl() -> Result<()> {
         tests.first().unwrap().scenarios.len()
     );
 
-    let mut results = IndexMap::new();
-    for i in tests.iter() {
-        bench(i, &mut results, allocator_kind, args.percent, is_bench);
+    let options = BenchOptions {
+        allocator_kind: allocators[0],
+        percent: percents[0],
+        is_bench,
+        repeat: args.repeat,
+        warmup: args.warmup,
+        isolate,
+        timeout,
+        seed: args.seed,
+    };
+
+    if child.is_some() {
+        // `select_child` left exactly one scenario of one impl.
+        let test = &tests[0];
+        let result = measure(test, &test.scenarios[0], options);
+        isolate::report(&ResultRecord::from(&result))?;
+        return Ok(ExitCode::SUCCESS);
     }
-    println!();
 
-    let mut output: Vec<[&dyn Display; 7]> = Vec::with_capacity(64);
-    for tests in results.values_mut() {
-        let min_run = tests.iter().map(|x| x.run_time.as_millis()).min().unwrap() as f64;
-        tests.sort_by_key(|x| x.run_time);
-        for i in tests {
-            i.extra = TestResultExtra {
-                run_time: format!("{:?}", i.run_time),
-                alloc_time: format!("{:?}", i.alloc_time),
-                slower_run: format!("{:.02}x", i.run_time.as_millis() as f64 / min_run),
-                max_memory: format_size(i.max_memory, BINARY),
+    // Seeded so that the groups of a scenario end up next to each other.
+    let mut results = Results::new();
+    for test in tests.iter() {
+        for i in test.scenarios.iter() {
+            for allocator in allocator_names.iter() {
+                for percent in percents.iter() {
+                    results.entry((i.name, *allocator, *percent)).or_default();
+                }
+            }
+        }
+    }
+    for allocator_kind in allocators.iter() {
+        for percent in percents.iter() {
+            let options = BenchOptions {
+                allocator_kind: *allocator_kind,
+                percent: *percent,
+                ..options
             };
+            for i in tests.iter() {
+                bench(i, &mut results, options)?;
+            }
+        }
+    }
+    println!();
 
-            output.push([
-                &i.scenario,
-                &i.impl_name,
-                &i.extra.run_time,
-                &i.extra.alloc_time,
-                &i.extra.slower_run,
-                &i.no_allocs,
-                &i.extra.max_memory,
-            ]);
+    if is_bench || isolate {
+        print_results(&mut results);
+        if allocators.len() > 1 {
+            print_allocator_view(&results, &allocator_names);
+        }
+        if percents.len() > 1 {
+            sweep::print_series(&results, &percents);
         }
-        let dashes = &"------";
-        let arr = array::from_fn(|_| dashes as &dyn Display);
-        output.push(arr);
     }
 
-    if is_bench {
-        create_table().print(output.iter());
+    let metadata = RunMetadata {
+        runner_version: env!("CARGO_PKG_VERSION"),
+        api_version: API_VERSION,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        allocators: allocator_names.clone(),
+        percents: percents.clone(),
+        bench: is_bench,
+        validation: is_validation,
+        repeat: args.repeat,
+        warmup: args.warmup,
+        isolate,
+        timeout_secs: args.timeout,
+        pin_cpu: args.pin_cpu,
+        nice: args.nice,
+        realtime: args.realtime,
+        params: args.params.clone(),
+        seed: args.seed,
+        impls: tests.len(),
+        scenarios: results.len() / allocators.len() / percents.len(),
+        manifest,
+    };
+    let set = ResultSet::new(metadata, &results);
+
+    if let Some(path) = args.output.as_deref() {
+        export::write_results(path, args.format, &set, args.group_by_scenario)?;
+    }
+    if let Some(dir) = args.charts.as_deref() {
+        svg::write_charts(dir, &results, &percents)?;
+    }
+    if let Some(name) = args.save_baseline.as_deref() {
+        baseline::save(&args.baseline_dir, name, &set)?;
+    }
+    if let Some(name) = args.compare.as_deref() {
+        let regressions = baseline::compare(&args.baseline_dir, name, &set, args.threshold)?;
+        if regressions > 0 {
+            return Ok(ExitCode::FAILURE);
+        }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let f = || {
         let start = Instant::now();
         let result = main_impl();
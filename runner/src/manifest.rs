@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::{fs, thread};
+
+/// How the runner was built; filled in by `build.rs`.
+#[derive(Serialize)]
+pub struct BuildInfo {
+    pub rustc: &'static str,
+    pub target: &'static str,
+    pub profile: &'static str,
+    pub opt_level: &'static str,
+    pub debug: &'static str,
+    pub lto: &'static str,
+    pub panic: &'static str,
+    pub git_commit: &'static str,
+    pub git_dirty: bool,
+    /// The `snmalloc-sys` version, or its requirement in the workspace if
+    /// `cargo metadata` failed.
+    pub snmalloc: &'static str,
+    /// The features `snmalloc-sys` was built with, e.g. `build_cmake`.
+    pub snmalloc_features: Vec<&'static str>,
+}
+
+/// The machine the results were measured on. Anything that can't be read is
+/// `None`.
+#[derive(Serialize)]
+pub struct MachineInfo {
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub hostname: Option<String>,
+    pub kernel: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cpus: Option<usize>,
+    pub memory_bytes: Option<u64>,
+    pub cpu_governor: Option<String>,
+}
+
+/// Where the impls of a run come from.
+#[derive(Serialize)]
+pub struct LibraryInfo {
+    pub prefix: String,
+    pub source: String,
+}
+
+/// Everything needed to trace results back to the setup that produced them.
+#[derive(Serialize)]
+pub struct Manifest {
+    pub build: BuildInfo,
+    pub machine: MachineInfo,
+    pub libraries: Vec<LibraryInfo>,
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let s = fs::read_to_string(path).ok()?;
+    Some(s.trim().to_string())
+}
+
+/// The value of the first `key: value` line of a `/proc` file.
+fn proc_field(path: &str, key: &str) -> Option<String> {
+    let s = fs::read_to_string(path).ok()?;
+    s.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k.trim() == key).then(|| v.trim().to_string())
+    })
+}
+
+impl BuildInfo {
+    pub fn current() -> BuildInfo {
+        BuildInfo {
+            rustc: env!("RUNNER_RUSTC"),
+            target: env!("RUNNER_TARGET"),
+            profile: env!("RUNNER_PROFILE"),
+            opt_level: env!("RUNNER_OPT_LEVEL"),
+            debug: env!("RUNNER_DEBUG"),
+            lto: env!("RUNNER_LTO"),
+            // Build scripts only see the target's default panic strategy.
+            panic: if cfg!(panic = "abort") {
+                "abort"
+            } else {
+                "unwind"
+            },
+            git_commit: env!("RUNNER_GIT_COMMIT"),
+            git_dirty: env!("RUNNER_GIT_DIRTY") == "true",
+            snmalloc: env!("RUNNER_SNMALLOC"),
+            snmalloc_features: env!("RUNNER_SNMALLOC_FEATURES")
+                .split(',')
+                .filter(|x| !x.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl MachineInfo {
+    pub fn current() -> MachineInfo {
+        let memory_bytes = proc_field("/proc/meminfo", "MemTotal").and_then(|x| {
+            let kib: u64 = x.strip_suffix("kB")?.trim().parse().ok()?;
+            Some(kib * 1024)
+        });
+
+        MachineInfo {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            hostname: read_trimmed("/proc/sys/kernel/hostname"),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            cpu_model: proc_field("/proc/cpuinfo", "model name"),
+            cpus: thread::available_parallelism().ok().map(|x| x.get()),
+            memory_bytes,
+            cpu_governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new(libraries: Vec<LibraryInfo>) -> Manifest {
+        Manifest {
+            build: BuildInfo::current(),
+            machine: MachineInfo::current(),
+            libraries,
+        }
+    }
+
+    /// The part of the manifest shown in the header of a run.
+    pub fn print(&self) {
+        let unknown = |x: &Option<String>| x.clone().unwrap_or_else(|| "unknown".to_string());
+        let build = &self.build;
+        let machine = &self.machine;
+
+        println!("rustc: {}", build.rustc);
+        println!(
+            "commit: {}{}",
+            build.git_commit,
+            if build.git_dirty { " (dirty)" } else { "" }
+        );
+        println!(
+            "profile: {} (opt-level {}, lto {}, panic {})",
+            build.profile, build.opt_level, build.lto, build.panic
+        );
+        println!(
+            "snmalloc-sys: {} [{}]",
+            build.snmalloc,
+            build.snmalloc_features.join(", ")
+        );
+        println!(
+            "cpu: {} x {}",
+            unknown(&machine.cpu_model),
+            machine.cpus.unwrap_or(0)
+        );
+        println!(
+            "kernel: {} {} ({})",
+            machine.os,
+            unknown(&machine.kernel),
+            machine.arch
+        );
+    }
+}
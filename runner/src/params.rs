@@ -0,0 +1,157 @@
+use crate::{ScenarioData, TestData};
+use anyhow::{bail, Context, Result};
+
+/// A `--param scenario.name=value` given on the command line.
+pub struct Override {
+    scenario: String,
+    name: String,
+    value: String,
+}
+
+impl Override {
+    pub fn parse(s: &str) -> Result<Override> {
+        let (key, value) = s
+            .split_once('=')
+            .with_context(|| format!("expected `scenario.name=value`, got `{s}`"))?;
+        let (scenario, name) = key
+            .split_once('.')
+            .filter(|(x, y)| !x.trim().is_empty() && !y.trim().is_empty())
+            .with_context(|| format!("expected `scenario.name=value`, got `{s}`"))?;
+        Ok(Override {
+            scenario: scenario.trim().to_string(),
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+/// Sets the parameter values of every scenario an override applies to, in
+/// every impl. An override that applies to no scenario is an error, as it is
+/// most likely a typo, and so is overriding a parameter twice.
+pub fn apply(tests: &mut [TestData], overrides: &[Override]) -> Result<()> {
+    for (index, i) in overrides.iter().enumerate() {
+        let same = |x: &Override| x.scenario == i.scenario && x.name == i.name;
+        if overrides[..index].iter().any(same) {
+            bail!("`{}.{}` is overridden more than once", i.scenario, i.name);
+        }
+
+        let mut found = false;
+        for scenario in tests.iter_mut().flat_map(|x| x.scenarios.iter_mut()) {
+            if scenario.name != i.scenario {
+                continue;
+            }
+            let Some(index) = scenario
+                .params
+                .iter()
+                .position(|x| unsafe { x.name() } == i.name)
+            else {
+                continue;
+            };
+
+            let param = &scenario.params[index];
+            scenario.values[index] = param
+                .parse(&i.value)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("invalid value for `{}.{}`", i.scenario, i.name))?;
+            found = true;
+        }
+        if !found {
+            bail!("no scenario `{}` with a parameter `{}`", i.scenario, i.name);
+        }
+    }
+    Ok(())
+}
+
+/// The parameters of a scenario with their current values and ranges, as
+/// shown by `list`.
+pub fn describe(scenario: &ScenarioData) -> String {
+    scenario
+        .params
+        .iter()
+        .zip(scenario.values.iter())
+        .map(|(param, value)| {
+            format!(
+                "{}={} ({}..={})",
+                unsafe { param.name() },
+                param.display(*value),
+                param.display(param.min),
+                param.display(param.max)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+    use tests_api::{params::RawParam, Handle, RawScenarioInit, RawScenarioKind};
+
+    unsafe extern "C" fn new(_: RawScenarioInit) -> Handle {
+        ptr::null_mut()
+    }
+
+    unsafe extern "C" fn nop(_: Handle) {}
+
+    fn tests() -> Vec<TestData> {
+        let params: &'static [RawParam] = Box::leak(Box::new([
+            RawParam::u64("size", 100, 1, 1000),
+            RawParam::f64("ratio", 0.5, 0.0, 1.0),
+        ]));
+        let scenario = ScenarioData {
+            name: "sum",
+            kind: RawScenarioKind::Bench,
+            new,
+            run: nop,
+            drop: nop,
+            params,
+            values: params.iter().map(|x| x.default_value).collect(),
+        };
+        vec![TestData {
+            name: "rust_rc_impl".to_string(),
+            short_name: "rust_rc_impl",
+            source: "built-in".to_string(),
+            scenarios: vec![scenario],
+        }]
+    }
+
+    fn apply_all(overrides: &[&str]) -> Result<Vec<TestData>> {
+        let overrides = overrides
+            .iter()
+            .map(|x| Override::parse(x))
+            .collect::<Result<Vec<_>>>()?;
+        let mut tests = tests();
+        apply(&mut tests, &overrides)?;
+        Ok(tests)
+    }
+
+    fn error(overrides: &[&str]) -> String {
+        format!("{:#}", apply_all(overrides).err().unwrap())
+    }
+
+    #[test]
+    fn overrides_set_values() {
+        let tests = apply_all(&["sum.size=10", " sum.ratio = 0.25 "]).unwrap();
+        let values = &tests[0].scenarios[0].values;
+        assert_eq!(unsafe { values[0].u64 }, 10);
+        assert_eq!(unsafe { values[1].f64 }, 0.25);
+    }
+
+    #[test]
+    fn malformed_overrides_are_errors() {
+        for i in ["sum.size", "size=10", "=10", ".size=10", "sum.=10"] {
+            assert!(
+                error(&[i]).contains("expected `scenario.name=value`"),
+                "{i}"
+            );
+        }
+        assert!(error(&["sum.depth=10"]).contains("no scenario `sum` with a parameter `depth`"));
+        assert!(error(&["push.size=10"]).contains("no scenario `push`"));
+        assert!(error(&["sum.size=ten"]).contains("`ten` isn't an integer"));
+        assert!(error(&["sum.size=-1"]).contains("isn't an integer"));
+        assert!(error(&["sum.ratio=x"]).contains("`x` isn't a number"));
+        assert!(error(&["sum.size=0"]).contains("0 is out of range 1..=1000"));
+        assert!(error(&["sum.size=1", "sum.size=2"]).contains("more than once"));
+    }
+}
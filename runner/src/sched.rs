@@ -0,0 +1,91 @@
+//! Process level knobs that make runs on shared machines less noisy. They are
+//! inherited by the children of `--isolate`.
+
+use anyhow::{bail, Result};
+use std::fs;
+
+#[cfg(target_os = "linux")]
+fn check(result: libc::c_int, what: &str) -> Result<()> {
+    if result != 0 {
+        bail!("couldn't {what}: {}", std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Restricts the runner to `cpu`.
+#[cfg(target_os = "linux")]
+pub fn pin_cpu(cpu: usize) -> Result<()> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        bail!("cpu {cpu} is out of range");
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        let result = libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set);
+        check(result, &format!("pin the runner to cpu {cpu}"))
+    }
+}
+
+/// Changes the nice value of the runner; negative values need privileges.
+#[cfg(target_os = "linux")]
+pub fn set_nice(nice: i32) -> Result<()> {
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) };
+    check(result, &format!("set the nice value to {nice}"))
+}
+
+/// Switches the runner to `SCHED_FIFO` with `priority` (1 to 99). A scenario
+/// that never yields can starve its core, so this is best combined with
+/// `--pin-cpu`.
+#[cfg(target_os = "linux")]
+pub fn set_realtime(priority: i32) -> Result<()> {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    let result = unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) };
+    check(result, &format!("switch to SCHED_FIFO priority {priority}"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_cpu(_: usize) -> Result<()> {
+    bail!("--pin-cpu is only supported on Linux")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_nice(_: i32) -> Result<()> {
+    bail!("--nice is only supported on Linux")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_realtime(_: i32) -> Result<()> {
+    bail!("--realtime is only supported on Linux")
+}
+
+/// Warns about the cpus whose frequency governor isn't `performance`: `cpu`
+/// if the runner is pinned, otherwise every cpu. Silent where cpufreq isn't
+/// exposed, e.g. in most VMs.
+pub fn check_governor(cpu: Option<usize>) {
+    let governor = |cpu: usize| {
+        let path = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/scaling_governor");
+        fs::read_to_string(path).ok().map(|x| x.trim().to_string())
+    };
+
+    let cpus: Vec<usize> = match cpu {
+        Some(x) => vec![x],
+        None => {
+            let count = std::thread::available_parallelism().map_or(1, |x| x.get());
+            (0..count).collect()
+        }
+    };
+    let slow: Vec<String> = cpus
+        .into_iter()
+        .filter_map(|cpu| Some((cpu, governor(cpu)?)))
+        .filter(|(_, governor)| governor != "performance")
+        .map(|(cpu, governor)| format!("cpu{cpu}: {governor}"))
+        .collect();
+    if !slow.is_empty() {
+        println!(
+            "warning: cpu frequency governor isn't `performance` ({}); results may be noisy",
+            slow.join(", ")
+        );
+    }
+}
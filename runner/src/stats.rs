@@ -0,0 +1,213 @@
+use std::{collections::BTreeMap, time::Duration};
+
+#[derive(Clone, Copy, Default)]
+pub struct Summary {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+    pub p95: Duration,
+}
+
+impl Summary {
+    pub fn new(samples: &[Duration]) -> Summary {
+        if samples.is_empty() {
+            return Summary::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let n = sorted.len();
+
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        };
+        // nearest-rank percentile
+        let p95 = sorted[(n * 95).div_ceil(100) - 1];
+
+        let mean = sorted.iter().map(|x| x.as_secs_f64()).sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            sorted
+                .iter()
+                .map(|x| (x.as_secs_f64() - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        Summary {
+            min: sorted[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+            p95,
+        }
+    }
+}
+
+/// What the allocations of a run add up to. Runs of the same scenario
+/// should agree; when they don't, the largest count is kept, as for the
+/// memory peak.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Counts {
+    pub no_allocs: usize,
+    pub no_reallocs: usize,
+    pub bytes_copied: usize,
+    pub max_memory: usize,
+}
+
+impl Counts {
+    pub fn max(self, other: Counts) -> Counts {
+        Counts {
+            no_allocs: self.no_allocs.max(other.no_allocs),
+            no_reallocs: self.no_reallocs.max(other.no_reallocs),
+            bytes_copied: self.bytes_copied.max(other.bytes_copied),
+            max_memory: self.max_memory.max(other.max_memory),
+        }
+    }
+}
+
+/// The metrics a scenario reported, see `tests_api::metrics`.
+pub type Metrics = BTreeMap<String, f64>;
+
+/// Writes metrics as JSON numbers, except NaN and the infinities, which JSON
+/// lacks: `serde_json` would write them as `null` and fail to read that back.
+/// They become the strings `NaN`, `inf` and `-inf` instead; a `null` from
+/// older results reads as NaN.
+pub mod metrics_serde {
+    use super::Metrics;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(metrics: &Metrics, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(metrics.iter().map(|(name, &x)| {
+            let value = match x.is_finite() {
+                true => Value::Number(x),
+                false => Value::Text(x.to_string()),
+            };
+            (name, value)
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Metrics, D::Error> {
+        let metrics = BTreeMap::<String, Option<Value>>::deserialize(deserializer)?;
+        metrics
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    None => f64::NAN,
+                    Some(Value::Number(x)) => x,
+                    Some(Value::Text(x)) => x
+                        .parse()
+                        .map_err(|_| de::Error::custom(format!("`{x}` is not a metric")))?,
+                };
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
+
+/// The mean of every metric over the samples that reported it.
+pub fn mean_metrics(samples: &[Metrics]) -> Metrics {
+    let mut sums: BTreeMap<&str, (f64, usize)> = BTreeMap::new();
+    for (name, value) in samples.iter().flatten() {
+        let sum = sums.entry(name).or_default();
+        sum.0 += value;
+        sum.1 += 1;
+    }
+    sums.into_iter()
+        .map(|(name, (sum, n))| (name.to_string(), sum / n as f64))
+        .collect()
+}
+
+/// Whole numbers without decimals, anything else with three.
+pub fn format_metric(x: f64) -> String {
+    if x.fract() == 0.0 && x.abs() < 1e15 {
+        format!("{x:.0}")
+    } else {
+        format!("{x:.3}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(x: &[u64]) -> Vec<Duration> {
+        x.iter().map(|&x| Duration::from_millis(x)).collect()
+    }
+
+    fn assert_ms(duration: Duration, expected: f64) {
+        let error = (duration.as_secs_f64() * 1000.0 - expected).abs();
+        assert!(error < 1e-6, "{duration:?} is not {expected}ms");
+    }
+
+    #[test]
+    fn summary_of_odd_samples() {
+        let summary = Summary::new(&ms(&[3, 1, 2]));
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.median, Duration::from_millis(2));
+        assert_eq!(summary.p95, Duration::from_millis(3));
+        assert_ms(summary.mean, 2.0);
+        assert_ms(summary.stddev, 1.0);
+    }
+
+    #[test]
+    fn summary_of_even_samples() {
+        let summary = Summary::new(&ms(&[4, 1, 3, 2]));
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.median, Duration::from_micros(2500));
+        assert_eq!(summary.p95, Duration::from_millis(4));
+        assert_ms(summary.mean, 2.5);
+        assert_ms(summary.stddev, (5.0f64 / 3.0).sqrt());
+
+        // The 95th of 20 samples is the 19th, not the largest.
+        let summary = Summary::new(&ms(&(1..=20).collect::<Vec<_>>()));
+        assert_eq!(summary.p95, Duration::from_millis(19));
+    }
+
+    #[test]
+    fn summary_of_one_sample() {
+        let summary = Summary::new(&ms(&[5]));
+        for i in [summary.min, summary.median, summary.p95] {
+            assert_eq!(i, Duration::from_millis(5));
+        }
+        assert_ms(summary.mean, 5.0);
+        assert_eq!(summary.stddev, Duration::ZERO);
+        assert_eq!(Summary::new(&[]).median, Duration::ZERO);
+    }
+
+    #[test]
+    fn counts_keep_the_largest() {
+        let a = Counts {
+            no_allocs: 10,
+            no_reallocs: 2,
+            bytes_copied: 64,
+            max_memory: 100,
+        };
+        let b = Counts {
+            no_allocs: 12,
+            no_reallocs: 1,
+            bytes_copied: 64,
+            max_memory: 90,
+        };
+        let expected = Counts {
+            no_allocs: 12,
+            no_reallocs: 2,
+            bytes_copied: 64,
+            max_memory: 100,
+        };
+        assert_eq!(a.max(b), expected);
+        assert_eq!(b.max(a), expected);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::Results;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use std::{fmt::Write, fs, path::Path};
+
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 500.0;
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 220.0;
+const TOP: f64 = 40.0;
+const BOTTOM: f64 = 110.0;
+
+const COLORS: [&str; 12] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#bab0ac", "#1f77b4", "#8c564b",
+];
+
+/// A named row of values; `None` for runs that failed.
+type Values<'x> = (&'x str, Vec<Option<f64>>);
+
+/// The color of `name`, by its place in `names`, so that an impl has the same
+/// color in every chart.
+fn color(names: &[&str], name: &str) -> &'static str {
+    let index = names.iter().position(|x| *x == name).unwrap_or(0);
+    COLORS[index % COLORS.len()]
+}
+
+/// `name` with everything but letters, digits, `-`, `_` and `.` replaced by
+/// `_`, so that it is a file name everywhere.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|x| match x {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => x,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Rounds `max` up to a multiple of 1, 2 or 5 times a power of ten and
+/// returns it with the step between ticks.
+fn nice_scale(max: f64) -> (f64, f64) {
+    if max <= 0.0 {
+        return (1.0, 0.2);
+    }
+    let rough = max / 5.0;
+    let power = 10f64.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|x| x * power)
+        .find(|x| *x >= rough)
+        .unwrap();
+    ((max / step).ceil() * step, step)
+}
+
+struct Svg {
+    out: String,
+}
+
+impl Svg {
+    fn new(title: &str) -> Svg {
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="12">"#
+        )
+        .unwrap();
+        writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        let mut svg = Svg { out };
+        svg.text(WIDTH / 2.0, 20.0, "middle", 16, &escape(title));
+        svg
+    }
+
+    fn text(&mut self, x: f64, y: f64, anchor: &str, size: u32, text: &str) {
+        writeln!(
+            self.out,
+            r#"<text x="{x:.1}" y="{y:.1}" text-anchor="{anchor}" font-size="{size}">{text}</text>"#
+        )
+        .unwrap();
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, color: &str) {
+        writeln!(
+            self.out,
+            r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="{color}"/>"#
+        )
+        .unwrap();
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: &str) {
+        writeln!(
+            self.out,
+            r#"<rect x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{height:.1}" fill="{color}"/>"#
+        )
+        .unwrap();
+    }
+
+    /// The y axis from 0 to `max` with a grid line at every tick.
+    fn y_axis(&mut self, max: f64, step: f64, label: &str) {
+        let bottom = HEIGHT - BOTTOM;
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        let ticks = (max / step).round() as usize;
+        for i in 0..=ticks {
+            let tick = i as f64 * step;
+            let y = bottom - tick / max * (bottom - TOP);
+            self.line(LEFT, y, WIDTH - RIGHT, y, "#dddddd");
+            self.text(
+                LEFT - 6.0,
+                y + 4.0,
+                "end",
+                11,
+                &format!("{tick:.decimals$}"),
+            );
+        }
+        self.line(LEFT, TOP, LEFT, bottom, "black");
+        self.line(LEFT, bottom, WIDTH - RIGHT, bottom, "black");
+        writeln!(
+            self.out,
+            r#"<text transform="translate(18 {:.1}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            (TOP + bottom) / 2.0,
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    /// Lists `shown`, colored by their place in `names`.
+    fn legend(&mut self, names: &[&str], shown: &[&str]) {
+        let x = WIDTH - RIGHT + 16.0;
+        for (index, name) in shown.iter().enumerate() {
+            let y = TOP + index as f64 * 18.0;
+            self.rect(x, y, 12.0, 12.0, color(names, name));
+            self.text(x + 18.0, y + 10.0, "start", 12, &escape(name));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("</svg>\n");
+        self.out
+    }
+}
+
+/// Bars per impl grouped per scenario. A missing value (a failed run) leaves
+/// a gap.
+fn bar_chart(title: &str, y_label: &str, impls: &[&str], groups: &[Values]) -> String {
+    let mut svg = Svg::new(title);
+    let max = groups
+        .iter()
+        .flat_map(|(_, x)| x.iter().flatten())
+        .fold(0.0, |a: f64, b| a.max(*b));
+    let (max, step) = nice_scale(max);
+    svg.y_axis(max, step, y_label);
+
+    let bottom = HEIGHT - BOTTOM;
+    let group_width = (WIDTH - RIGHT - LEFT) / groups.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / impls.len().max(1) as f64;
+    for (index, (name, values)) in groups.iter().enumerate() {
+        let start = LEFT + index as f64 * group_width + group_width * 0.1;
+        for (column, value) in values.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let height = value / max * (bottom - TOP);
+            let x = start + column as f64 * bar_width;
+            let color = color(impls, impls[column]);
+            svg.rect(x, bottom - height, bar_width, height, color);
+        }
+
+        let x = LEFT + (index as f64 + 0.5) * group_width;
+        writeln!(
+            svg.out,
+            r#"<text transform="translate({x:.1} {:.1}) rotate(-35)" text-anchor="end">{}</text>"#,
+            bottom + 14.0,
+            escape(name)
+        )
+        .unwrap();
+    }
+
+    svg.legend(impls, impls);
+    svg.finish()
+}
+
+/// One line per impl over the percents of a sweep, colored like in the bar
+/// charts of `impls`. Missing values break the line.
+fn line_chart(
+    title: &str,
+    y_label: &str,
+    impls: &[&str],
+    percents: &[u32],
+    series: &[Values],
+) -> String {
+    let mut svg = Svg::new(title);
+    let max = series
+        .iter()
+        .flat_map(|(_, x)| x.iter().flatten())
+        .fold(0.0, |a: f64, b| a.max(*b));
+    let (max, step) = nice_scale(max);
+    svg.y_axis(max, step, y_label);
+
+    let bottom = HEIGHT - BOTTOM;
+    let (first, last) = (percents[0] as f64, *percents.last().unwrap() as f64);
+    let x_of = |percent: u32| {
+        let span = (last - first).max(1.0);
+        LEFT + (percent as f64 - first) / span * (WIDTH - RIGHT - LEFT)
+    };
+    let y_of = |value: f64| bottom - value / max * (bottom - TOP);
+
+    for percent in percents {
+        let x = x_of(*percent);
+        svg.line(x, bottom, x, bottom + 4.0, "black");
+        svg.text(x, bottom + 18.0, "middle", 11, &format!("{percent}%"));
+    }
+    svg.text(
+        (LEFT + WIDTH - RIGHT) / 2.0,
+        bottom + 40.0,
+        "middle",
+        12,
+        "percent of iterations",
+    );
+
+    for (name, values) in series.iter() {
+        let color = color(impls, name);
+        let points: Vec<Option<(f64, f64)>> = percents
+            .iter()
+            .zip(values)
+            .map(|(p, v)| v.map(|v| (x_of(*p), y_of(v))))
+            .collect();
+
+        for segment in points.split(|x| x.is_none()) {
+            let segment: Vec<String> = segment
+                .iter()
+                .flatten()
+                .map(|(x, y)| format!("{x:.1},{y:.1}"))
+                .collect();
+            if segment.len() > 1 {
+                writeln!(
+                    svg.out,
+                    r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                    segment.join(" ")
+                )
+                .unwrap();
+            }
+        }
+        for (x, y) in points.iter().flatten() {
+            writeln!(
+                svg.out,
+                r#"<circle cx="{x:.1}" cy="{y:.1}" r="3" fill="{color}"/>"#
+            )
+            .unwrap();
+        }
+    }
+
+    let names: Vec<&str> = series.iter().map(|(x, _)| *x).collect();
+    svg.legend(impls, &names);
+    svg.finish()
+}
+
+fn write(dir: &Path, name: &str, svg: String) -> Result<()> {
+    let path = dir.join(format!("{}.svg", file_name(name)));
+    fs::write(&path, svg).with_context(|| format!("couldn't write `{}`", path.display()))
+}
+
+/// Writes a bar chart of the run times relative to the fastest impl for every
+/// allocator and percent, and with more than one percent a line chart of the
+/// median run times for every scenario and allocator.
+pub fn write_charts(dir: &Path, results: &Results, percents: &[u32]) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("couldn't create chart directory `{}`", dir.display()))?;
+
+    let mut impls: Vec<&str> = Vec::new();
+    for i in results.values().flatten() {
+        if !impls.contains(&i.impl_name) {
+            impls.push(i.impl_name);
+        }
+    }
+    let column = |name: &str| impls.iter().position(|x| *x == name).unwrap();
+
+    let mut bars: IndexMap<(&str, u32), Vec<Values>> = IndexMap::new();
+    let mut lines: IndexMap<(&str, &str), Vec<Vec<Option<f64>>>> = IndexMap::new();
+    for ((scenario, allocator, percent), tests) in results.iter() {
+        let min_run = tests
+            .iter()
+            .filter(|x| x.status.is_ok())
+            .map(|x| x.run_time.median.as_nanos())
+            .min()
+            .unwrap_or(1)
+            .max(1);
+
+        let mut slower = vec![None; impls.len()];
+        let series = lines
+            .entry((scenario, allocator))
+            .or_insert_with(|| vec![vec![None; percents.len()]; impls.len()]);
+        let index = percents.iter().position(|x| x == percent).unwrap();
+        for i in tests.iter().filter(|x| x.status.is_ok()) {
+            let median = i.run_time.median.as_secs_f64();
+            let ratio = i.run_time.median.as_nanos() as f64 / min_run as f64;
+            slower[column(i.impl_name)] = Some(ratio);
+            series[column(i.impl_name)][index] = Some(median * 1000.0);
+        }
+        bars.entry((allocator, *percent))
+            .or_default()
+            .push((scenario, slower));
+    }
+
+    for ((allocator, percent), groups) in bars.iter() {
+        let title = format!("run time relative to the fastest impl ({allocator}, {percent}%)");
+        let svg = bar_chart(&title, "slower than fastest (x)", &impls, groups);
+        write(dir, &format!("bars_{allocator}_{percent}"), svg)?;
+    }
+
+    if percents.len() > 1 {
+        for ((scenario, allocator), series) in lines.iter() {
+            let series: Vec<Values> = impls
+                .iter()
+                .zip(series.iter())
+                .filter(|(_, x)| x.iter().any(|x| x.is_some()))
+                .map(|(name, x)| (*name, x.clone()))
+                .collect();
+            let title = format!("{scenario} ({allocator})");
+            let y_label = "median run time (ms)";
+            let svg = line_chart(&title, y_label, &impls, percents, &series);
+            write(dir, &format!("sweep_{scenario}_{allocator}"), svg)?;
+        }
+    }
+
+    println!("charts written to {}", dir.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impls_keep_their_color() {
+        let impls = ["rust_rc_impl", "cpp_std_list", "rust_slotmap_impl"];
+        let series = [("rust_slotmap_impl", vec![Some(1.0), Some(2.0)])];
+        let svg = line_chart("sum", "ms", &impls, &[10, 20], &series);
+        let expected = color(&impls, "rust_slotmap_impl");
+        assert_eq!(expected, COLORS[2]);
+        assert!(svg.contains(&format!(r#"stroke="{expected}""#)));
+        assert!(!svg.contains(COLORS[0]));
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(file_name("sweep_a/b c_arena"), "sweep_a_b_c_arena");
+        assert_eq!(file_name("bars_..\\x:y*_100"), "bars_.._x_y__100");
+        assert_eq!(file_name("push-pages_1.5"), "push-pages_1.5");
+    }
+}
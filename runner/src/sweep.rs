@@ -0,0 +1,166 @@
+use crate::{Results, TestResult};
+use ascii_table::{Align, AsciiTable};
+use humansize::{format_size, BINARY};
+use indexmap::IndexMap;
+use std::time::Duration;
+
+/// Parses the argument of `--sweep`: comma separated percents, where an item
+/// can also be a range `start..end:step` that includes `end`.
+pub fn parse(s: &str) -> Vec<u32> {
+    let number = |x: &str| -> u32 {
+        match x.trim().parse() {
+            Ok(x) => x,
+            Err(_) => panic!("expected a percent in --sweep, got `{x}`"),
+        }
+    };
+
+    let mut percents = Vec::new();
+    for i in s.split(',') {
+        match i.split_once("..") {
+            Some((start, rest)) => {
+                let Some((end, step)) = rest.split_once(':') else {
+                    panic!("expected `start..end:step` in --sweep, got `{i}`");
+                };
+                let (start, end, step) = (number(start), number(end), number(step));
+                if step == 0 || start > end {
+                    panic!("empty range `{i}` in --sweep");
+                }
+                percents.extend((start..=end).step_by(step as usize));
+                percents.push(end);
+            }
+            None => percents.push(number(i)),
+        }
+    }
+
+    percents.sort_unstable();
+    percents.dedup();
+    percents
+}
+
+/// The results of one impl at every percent of the sweep, by column.
+type Series<'r, 'x> = Vec<Option<&'r TestResult<'x>>>;
+
+fn median(x: &Option<&TestResult>) -> Option<Duration> {
+    x.filter(|x| x.status.is_ok()).map(|x| x.run_time.median)
+}
+
+/// Prints, for every scenario and allocator, the median run time and max
+/// memory of each impl against the percent, then the points where one impl
+/// overtakes another.
+pub fn print_series(results: &Results, percents: &[u32]) {
+    let mut groups: IndexMap<(&str, &str), IndexMap<&str, Series>> = IndexMap::new();
+    for ((scenario, allocator, percent), tests) in results.iter() {
+        let column = percents.iter().position(|x| x == percent).unwrap();
+        let group = groups.entry((scenario, allocator)).or_default();
+        for i in tests {
+            let series = group
+                .entry(i.impl_name)
+                .or_insert_with(|| vec![None; percents.len()]);
+            series[column] = Some(i);
+        }
+    }
+
+    for ((scenario, allocator), group) in groups {
+        let mut ascii_table = AsciiTable::default();
+        ascii_table.set_max_width(200);
+        ascii_table
+            .column(0)
+            .set_header("name")
+            .set_align(Align::Center);
+        for (index, percent) in percents.iter().enumerate() {
+            ascii_table
+                .column(index + 1)
+                .set_header(format!("{percent}%"))
+                .set_align(Align::Right);
+        }
+
+        let mut output: Vec<Vec<String>> = Vec::with_capacity(group.len());
+        for (impl_name, series) in group.iter() {
+            let mut row = vec![impl_name.to_string()];
+            for i in series {
+                row.push(match i.filter(|x| x.status.is_ok()) {
+                    Some(x) => format!(
+                        "{:.2?} / {}",
+                        x.run_time.median,
+                        format_size(x.max_memory, BINARY)
+                    ),
+                    None => i.map_or("-".to_string(), |x| x.status.to_string()),
+                });
+            }
+            output.push(row);
+        }
+
+        println!("\n{scenario} with {allocator}: median run time / max memory");
+        ascii_table.print(output.iter());
+        print_crossovers(&group, percents);
+    }
+}
+
+/// Lists every pair of impls whose order by median run time flips between
+/// two neighbouring percents.
+fn print_crossovers(group: &IndexMap<&str, Series>, percents: &[u32]) {
+    let impls: Vec<(&&str, &Series)> = group.iter().collect();
+    for (index, (a, a_series)) in impls.iter().enumerate() {
+        for (b, b_series) in impls[index + 1..].iter() {
+            for i in 1..percents.len() {
+                let points = (
+                    median(&a_series[i - 1]),
+                    median(&b_series[i - 1]),
+                    median(&a_series[i]),
+                    median(&b_series[i]),
+                );
+                let (Some(a_before), Some(b_before), Some(a_after), Some(b_after)) = points else {
+                    continue;
+                };
+                if a_before == b_before || a_after == b_after {
+                    continue;
+                }
+                if (a_before < b_before) == (a_after < b_after) {
+                    continue;
+                }
+
+                let (faster, slower) = if a_after < b_after { (a, b) } else { (b, a) };
+                println!(
+                    "    {faster} overtakes {slower} between {}% and {}%",
+                    percents[i - 1],
+                    percents[i]
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_include_their_end() {
+        assert_eq!(parse("10..50:20"), [10, 30, 50]);
+        assert_eq!(parse("10..25:10"), [10, 20, 25]);
+        assert_eq!(parse("5..5:1"), [5]);
+    }
+
+    #[test]
+    fn percents_are_sorted_without_duplicates() {
+        assert_eq!(parse("100, 50,50,10..30:10,20"), [10, 20, 30, 50, 100]);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn zero_step_is_rejected() {
+        parse("10..50:0");
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn reversed_range_is_rejected() {
+        parse("50..10:10");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected `start..end:step`")]
+    fn range_without_step_is_rejected() {
+        parse("10..50");
+    }
+}
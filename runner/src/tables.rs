@@ -0,0 +1,292 @@
+use crate::{
+    export::{ResultRecord, ResultSet},
+    stats,
+};
+use anyhow::Result;
+use humansize::{format_size, BINARY};
+use indexmap::IndexMap;
+use std::{io::Write, time::Duration};
+
+/// A cell of a publication table; `bold` marks the best value of a group.
+struct Cell {
+    text: String,
+    bold: bool,
+}
+
+struct Group {
+    title: String,
+    rows: Vec<Vec<Cell>>,
+}
+
+struct Table<'x> {
+    headers: Vec<&'x str>,
+    right: Vec<bool>,
+    groups: Vec<Group>,
+}
+
+fn time(ns: u64) -> String {
+    format!("{:.2?}", Duration::from_nanos(ns))
+}
+
+/// Builds the rows shared by the LaTeX and Markdown emitters. Results are
+/// grouped by scenario, allocator and percent like the ascii table; within a
+/// group the lowest setup, run, teardown and alloc time, number of allocs and
+/// reallocs, bytes copied and memory are bold. Allocator and percent are only
+/// shown if they vary.
+fn build(set: &ResultSet, grouped: bool) -> Table<'_> {
+    let mut groups: IndexMap<(&str, &str, u32), Vec<&ResultRecord>> = IndexMap::new();
+    for i in set.results.iter() {
+        groups
+            .entry((&i.scenario, &i.allocator, i.percent))
+            .or_default()
+            .push(i);
+    }
+
+    let varies = |f: fn(&ResultRecord) -> String| {
+        let mut values = set.results.iter().map(f);
+        let first = values.next();
+        values.any(|x| Some(x) != first)
+    };
+    let with_allocator = varies(|x| x.allocator.clone());
+    let with_percent = varies(|x| x.percent.to_string());
+    let with_status = set.results.iter().any(|x| !x.status.is_ok());
+    // Grouped tables name the allocator and percent in the group title.
+    let allocator_column = with_allocator && !grouped;
+    let percent_column = with_percent && !grouped;
+
+    let mut headers = Vec::new();
+    let mut right = Vec::new();
+    let mut column = |header, is_right| {
+        headers.push(header);
+        right.push(is_right);
+    };
+    if !grouped {
+        column("scenario", false);
+    }
+    column("impl", false);
+    if allocator_column {
+        column("allocator", false);
+    }
+    if percent_column {
+        column("percent", true);
+    }
+    if with_status {
+        column("status", false);
+    }
+    for i in [
+        "setup",
+        "time",
+        "mean ± σ",
+        "teardown",
+        "alloc time",
+        "slower",
+        "allocs",
+        "reallocs",
+        "copied",
+        "max memory",
+    ] {
+        column(i, true);
+    }
+    let metric_names = set.metric_names();
+    for i in metric_names.iter() {
+        column(i, true);
+    }
+
+    let mut table = Table {
+        headers,
+        right,
+        groups: Vec::new(),
+    };
+    for ((scenario, allocator, percent), mut records) in groups {
+        records.sort_by_key(|x| (!x.status.is_ok(), x.run_time.median_ns));
+        let ok = || records.iter().filter(|x| x.status.is_ok());
+        let best_setup = ok().map(|x| x.setup_time.median_ns).min();
+        let best_run = ok().map(|x| x.run_time.median_ns).min();
+        let best_teardown = ok().map(|x| x.teardown_time.median_ns).min();
+        let best_alloc = ok().map(|x| x.alloc_time.median_ns).min();
+        let best_allocs = ok().map(|x| x.no_allocs).min();
+        let best_reallocs = ok().map(|x| x.no_reallocs).min();
+        let best_copied = ok().map(|x| x.bytes_copied).min();
+        let best_memory = ok().map(|x| x.max_memory).min();
+
+        let mut rows = Vec::with_capacity(records.len());
+        for i in records.iter() {
+            let cell = |text: String, bold: bool| Cell { text, bold };
+            let mut row = Vec::new();
+            if !grouped {
+                row.push(cell(scenario.to_string(), false));
+            }
+            row.push(cell(i.impl_name.clone(), false));
+            if allocator_column {
+                row.push(cell(allocator.to_string(), false));
+            }
+            if percent_column {
+                row.push(cell(format!("{percent}%"), false));
+            }
+            if with_status {
+                row.push(cell(i.status.to_string(), false));
+            }
+
+            if !i.status.is_ok() {
+                let columns = 10 + metric_names.len();
+                row.extend((0..columns).map(|_| cell("-".to_string(), false)));
+                rows.push(row);
+                continue;
+            }
+            let run = &i.run_time;
+            let slower = run.median_ns as f64 / best_run.unwrap_or(1).max(1) as f64;
+            row.push(cell(
+                time(i.setup_time.median_ns),
+                Some(i.setup_time.median_ns) == best_setup,
+            ));
+            row.push(cell(time(run.median_ns), Some(run.median_ns) == best_run));
+            row.push(cell(
+                format!("{} ± {}", time(run.mean_ns), time(run.stddev_ns)),
+                false,
+            ));
+            row.push(cell(
+                time(i.teardown_time.median_ns),
+                Some(i.teardown_time.median_ns) == best_teardown,
+            ));
+            row.push(cell(
+                time(i.alloc_time.median_ns),
+                Some(i.alloc_time.median_ns) == best_alloc,
+            ));
+            row.push(cell(format!("{slower:.2}x"), false));
+            row.push(cell(
+                i.no_allocs.to_string(),
+                Some(i.no_allocs) == best_allocs,
+            ));
+            row.push(cell(
+                i.no_reallocs.to_string(),
+                Some(i.no_reallocs) == best_reallocs,
+            ));
+            row.push(cell(
+                format_size(i.bytes_copied, BINARY),
+                Some(i.bytes_copied) == best_copied,
+            ));
+            row.push(cell(
+                format_size(i.max_memory, BINARY),
+                Some(i.max_memory) == best_memory,
+            ));
+            for name in metric_names.iter() {
+                let text = i.metrics.get(*name).map(|x| stats::format_metric(*x));
+                row.push(cell(text.unwrap_or_default(), false));
+            }
+            rows.push(row);
+        }
+
+        let mut title = scenario.to_string();
+        match (with_allocator, with_percent) {
+            (true, true) => title += &format!(" ({allocator}, {percent}%)"),
+            (true, false) => title += &format!(" ({allocator})"),
+            (false, true) => title += &format!(" ({percent}%)"),
+            (false, false) => {}
+        }
+        table.groups.push(Group { title, rows });
+    }
+    table
+}
+
+fn latex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '_' | '%' | '&' | '#' | '$' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            'µ' => out.push_str("\\textmu{}"),
+            '±' => out.push_str("$\\pm$"),
+            'σ' => out.push_str("$\\sigma$"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// A `booktabs` tabular with a `\midrule` between groups. Grouped tables get
+/// a caption row per group instead of a scenario column.
+pub fn write_latex(out: &mut impl Write, set: &ResultSet, grouped: bool) -> Result<()> {
+    let table = build(set, grouped);
+    let spec: String = table
+        .right
+        .iter()
+        .map(|x| if *x { 'r' } else { 'l' })
+        .collect();
+    let cells = |row: &[Cell]| -> String {
+        row.iter()
+            .map(|x| match x.bold {
+                true => format!("\\textbf{{{}}}", latex_escape(&x.text)),
+                false => latex_escape(&x.text),
+            })
+            .collect::<Vec<_>>()
+            .join(" & ")
+    };
+
+    writeln!(out, "% requires \\usepackage{{booktabs}}")?;
+    writeln!(out, "\\begin{{tabular}}{{{spec}}}")?;
+    writeln!(out, "\\toprule")?;
+    let headers: Vec<String> = table.headers.iter().map(|x| latex_escape(x)).collect();
+    writeln!(out, "{} \\\\", headers.join(" & "))?;
+    for group in table.groups.iter() {
+        writeln!(out, "\\midrule")?;
+        if grouped {
+            writeln!(
+                out,
+                "\\multicolumn{{{}}}{{l}}{{\\textit{{{}}}}} \\\\",
+                table.headers.len(),
+                latex_escape(&group.title)
+            )?;
+        }
+        for row in group.rows.iter() {
+            writeln!(out, "{} \\\\", cells(row))?;
+        }
+    }
+    writeln!(out, "\\bottomrule")?;
+    writeln!(out, "\\end{{tabular}}")?;
+    Ok(())
+}
+
+/// A GitHub flavored Markdown table, or one table per group under a heading.
+pub fn write_markdown(out: &mut impl Write, set: &ResultSet, grouped: bool) -> Result<()> {
+    let table = build(set, grouped);
+    let escape = |s: &str| s.replace('|', "\\|");
+    let header = |out: &mut dyn Write| -> Result<()> {
+        writeln!(out, "| {} |", table.headers.join(" | "))?;
+        let align: Vec<&str> = table
+            .right
+            .iter()
+            .map(|x| if *x { "---:" } else { ":---" })
+            .collect();
+        writeln!(out, "| {} |", align.join(" | "))?;
+        Ok(())
+    };
+
+    if !grouped {
+        header(out)?;
+    }
+    for group in table.groups.iter() {
+        if grouped {
+            writeln!(out, "### {}\n", escape(&group.title))?;
+            header(out)?;
+        }
+        for row in group.rows.iter() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|x| match x.bold {
+                    true => format!("**{}**", escape(&x.text)),
+                    false => escape(&x.text),
+                })
+                .collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+        if grouped {
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
@@ -7,17 +7,25 @@ mod solutions;
 use std::marker::PhantomData;
 
 use scenarios::Scenario;
-use tests_api::{Handle, RawImpl, RawLoadResult, RawScenario, RawScenarioInit, RawScenarioKind};
+use tests_api::{
+    raw_alloc::ForeignAllocator, Handle, RawImpl, RawLoadResult, RawScenario, RawScenarioInit,
+    RawScenarioKind, TheAlloc, API_VERSION,
+};
 
 use crate::scenarios::ScenarioInit;
 
 const fn sc<'x, S: Scenario<'x>>(name: &'static str, kind: RawScenarioKind) -> RawScenario {
     // TODO: + 'static?
     unsafe extern "C" fn new<'x, S: Scenario<'x>>(init: RawScenarioInit) -> Handle {
-        let alloc = &**init.alloc;
+        let alloc: &'static TheAlloc = ForeignAllocator::from_raw(init.alloc);
         let init = ScenarioInit {
             alloc,
             percent: init.percent,
+            metrics: &*init.metrics,
+            params: S::PARAMS,
+            values: std::slice::from_raw_parts(init.params, S::PARAMS.len()),
+            seed: init.seed,
+            validation: &*init.validation,
             _p: PhantomData
         };
         let s = Box::new(S::new(init));
@@ -26,17 +34,23 @@ const fn sc<'x, S: Scenario<'x>>(name: &'static str, kind: RawScenarioKind) -> R
         ptr as Handle
     }
     unsafe extern "C" fn run<'x, S: Scenario<'x>>(handle: Handle) {
-        let ptr = handle as *mut S;
-        let obj = Box::from_raw(ptr);
+        let obj = &mut *(handle as *mut S);
         obj.run();
     }
+    unsafe extern "C" fn drop<'x, S: Scenario<'x>>(handle: Handle) {
+        let ptr = handle as *mut S;
+        std::mem::drop(Box::from_raw(ptr));
+    }
 
     RawScenario {
         name: name.as_ptr(),
         name_size: name.len(),
         new: new::<S>,
         run: run::<S>,
+        drop: drop::<S>,
         kind,
+        params: S::PARAMS.as_ptr(),
+        params_count: S::PARAMS.len(),
     }
 }
 
@@ -53,7 +67,8 @@ macro_rules! list_impl {
 
         const SCENARIOS: &[RawScenario] = &[
             // validation
-            // sv::<UseAfterDelete<solutions::$name::Implementation<u64>>>("use_after_delete"),
+            sv::<UseAfterDelete<solutions::$name::Implementation<u64>>>("use_after_delete"),
+            sv::<UseAfterDeleteAndReinsert<solutions::$name::Implementation<u64>>>("use_after_delete_and_reinsert"),
             sv::<First<solutions::$name::Implementation<u64>>>("first"),
             sv::<Last<solutions::$name::Implementation<u64>>>("last"),
             sv::<Last<solutions::$name::Implementation<u64>>>("order"),
@@ -64,6 +79,7 @@ macro_rules! list_impl {
             sb::<AddFrontBack<solutions::$name::Implementation<u64>>>("add_front_back"),
             sb::<SearchMiddle<solutions::$name::Implementation<u64>>>("search_middle"),
             sb::<SumScenario<solutions::$name::Implementation<u64>>>("sum"),
+            sb::<IterateShuffled<solutions::$name::Implementation<u64>>>("iterate_shuffled"),
             sb::<PushDeleteOneScenario<solutions::$name::Implementation<u64>>>("push_delete_one"),
             sb::<PushScenario<solutions::$name::Implementation<u64>>>("push"),
             sb::<Fragmentation<solutions::$name::Implementation<u64>>>("fragmentation"),
@@ -79,6 +95,11 @@ macro_rules! list_impl {
     }};
 }
 
+#[no_mangle]
+pub extern "C" fn api_version() -> u32 {
+    API_VERSION
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn load_tests() -> RawLoadResult {
     const LIST_IMPLS: &[RawImpl] = &[
@@ -95,8 +116,5 @@ pub unsafe extern "C" fn load_tests() -> RawLoadResult {
         list_impl!(gen_arena_impl),
     ];
 
-    RawLoadResult {
-        list_impl: LIST_IMPLS.as_ptr(),
-        list_impl_count: LIST_IMPLS.len(),
-    }
+    RawLoadResult::new(LIST_IMPLS)
 }
@@ -1,27 +1,57 @@
 use std::{array, hint::black_box, marker::PhantomData};
-use tests_api::TheAlloc;
+use tests_api::{
+    metrics::RawMetrics,
+    params::{RawParam, RawParamKind, RawParamValue},
+    rng::Rng,
+    validation::RawValidation,
+    TheAlloc,
+};
 
 use crate::solutions::double_linked_list::DoubleLinkedList;
 
 pub struct ScenarioInit<'x> {
     pub alloc: &'static TheAlloc,
     pub percent: u32,
+    pub metrics: &'static RawMetrics,
+    /// The `Scenario::PARAMS` of the scenario and their values.
+    pub params: &'static [RawParam],
+    pub values: &'static [RawParamValue],
+    pub seed: u64,
+    pub validation: &'static RawValidation,
     pub _p: PhantomData<&'x ()>,
 }
 impl<'x> ScenarioInit<'x> {
-    fn percent_usize(&self, x: usize) -> usize {
-        x * self.percent as usize / 100
+    fn param(&self, name: &str, kind: RawParamKind) -> RawParamValue {
+        let index = self
+            .params
+            .iter()
+            .position(|x| unsafe { x.name() } == name)
+            .unwrap_or_else(|| panic!("no parameter `{name}`"));
+        assert!(self.params[index].kind == kind, "wrong kind of `{name}`");
+        self.values[index]
     }
-    fn percent_u64(&self, x: u64) -> u64 {
-        x * self.percent as u64 / 100
+    fn u64(&self, name: &str) -> u64 {
+        unsafe { self.param(name, RawParamKind::U64).u64 }
+    }
+    /// The parameter `name` scaled by the percent, but never below its
+    /// minimum.
+    fn scaled_u64(&self, name: &str) -> u64 {
+        let value = self.u64(name) * self.percent as u64 / 100;
+        let param = self.params.iter().find(|x| unsafe { x.name() } == name);
+        value.max(param.map_or(0, |x| unsafe { x.min.u64 }))
     }
 }
 
 pub trait Scenario<'x> {
     type Impl;
+    /// Knobs that can be set with `--param scenario.name=value`.
+    const PARAMS: &'static [RawParam] = &[];
 
     fn new(init: ScenarioInit<'x>) -> Self;
-    fn run(self);
+    /// Timed on its own; building and dropping the scenario are timed as
+    /// setup and teardown. Keep what `run` builds, like the list, in `self`
+    /// so that freeing it isn't part of the run time.
+    fn run(&mut self);
 }
 
 // ----------------------------------------------------------------------------
@@ -31,21 +61,27 @@ const ITERATIONS: u64 = 1_000_000;
 pub struct SumScenario<L> {
     list: L,
     iterations: usize,
+    metrics: &'static RawMetrics,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SumScenario<L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("size", 10_000_000, 1, 1_000_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        let iterations = init.percent_usize(10_000_000);
+        let iterations = init.scaled_u64("size") as usize;
         let mut list = L::new(init.alloc, iterations);
         for i in 1..=iterations {
             list.push_back(i as u64);
         }
-        Self { list, iterations }
+        Self {
+            list,
+            iterations,
+            metrics: init.metrics,
+        }
     }
 
-    fn run(self) {
-        let list = self.list;
+    fn run(&mut self) {
+        let list = &self.list;
         let mut sum = 0;
 
         let mut first = list.first();
@@ -57,6 +93,54 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SumScenario<L> {
 
         let iterations = self.iterations as u64;
         assert_eq!(sum, iterations * (iterations + 1) / 2);
+        self.metrics.report("checksum", sum as f64);
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+pub struct IterateShuffled<L> {
+    list: L,
+    size: u64,
+    metrics: &'static RawMetrics,
+}
+impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for IterateShuffled<L> {
+    type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("size", 1_000_000, 1, 100_000_000)];
+
+    fn new(init: ScenarioInit<'x>) -> Self {
+        // Half of the nodes are deleted in random order and pushed again, so
+        // where freed memory is reused the order of the list no longer follows
+        // the order of the memory.
+        let size = init.scaled_u64("size");
+        let mut rng = Rng::new(init.seed);
+        let mut list = L::new(init.alloc, size as usize);
+        let mut nodes: Vec<_> = (1..=size).map(|x| (list.push_back(x), x)).collect();
+        rng.shuffle(&mut nodes);
+        for (node, value) in nodes.drain(..nodes.len() / 2) {
+            unsafe { list.delete(node) };
+            list.push_back(value);
+        }
+
+        Self {
+            list,
+            size,
+            metrics: init.metrics,
+        }
+    }
+
+    fn run(&mut self) {
+        let list = &self.list;
+        let mut sum = 0;
+
+        let mut first = list.first();
+        while let Some(element) = first {
+            sum += list.value(element.clone()).unwrap();
+            first = list.next(element);
+        }
+
+        assert_eq!(sum, self.size * (self.size + 1) / 2);
+        self.metrics.report("checksum", sum as f64);
     }
 }
 
@@ -64,27 +148,28 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SumScenario<L> {
 
 pub struct PushDeleteOneScenario<'x, L> {
     init: ScenarioInit<'x>,
-    _p: PhantomData<L>,
+    list: Option<L>,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for PushDeleteOneScenario<'x, L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] =
+        &[RawParam::u64("iterations", ITERATIONS, 1, 1_000_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        Self {
-            init,
-            _p: PhantomData,
-        }
+        Self { init, list: None }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 1);
-        let iterations = self.init.percent_u64(ITERATIONS);
+        let iterations = self.init.scaled_u64("iterations");
         for i in 1..=iterations {
             let node = list.push_back(i);
             unsafe { list.delete(node) };
         }
 
         assert_eq!(list.first(), None);
+
+        self.list = Some(list);
     }
 }
 
@@ -92,26 +177,27 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for PushDeleteOneScenario<'x
 
 pub struct PushScenario<'x, L> {
     init: ScenarioInit<'x>,
-    _p: PhantomData<L>,
+    list: Option<L>,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for PushScenario<'x, L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("size", 10_000_000, 1, 1_000_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        Self {
-            init,
-            _p: PhantomData,
-        }
+        Self { init, list: None }
     }
 
-    fn run(self) {
-        let iterations = self.init.percent_u64(10_000_000);
+    fn run(&mut self) {
+        let iterations = self.init.scaled_u64("size");
         let mut list = L::new(self.init.alloc, iterations as usize);
         for i in 1..=iterations {
             list.push_back(i);
         }
 
         assert_eq!(list.value(list.last().unwrap()), Some(&iterations));
+        self.init.metrics.report("nodes", iterations as f64);
+
+        self.list = Some(list);
     }
 }
 
@@ -119,27 +205,30 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for PushScenario<'x, L> {
 
 pub struct Fragmentation<'x, L> {
     init: ScenarioInit<'x>,
-    _p: PhantomData<L>,
+    list: Option<L>,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for Fragmentation<'x, L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[
+        RawParam::u64("rounds", 1_000, 1, 1_000_000),
+        RawParam::u64("batch", 10_000, 1, 100_000_000),
+    ];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        Self {
-            init,
-            _p: PhantomData,
-        }
+        Self { init, list: None }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 1000);
-        let iterations = self.init.percent_u64(1_000);
+        let iterations = self.init.scaled_u64("rounds");
+        let batch = self.init.u64("batch");
+        let mut deleted = 0;
         for _ in 0..=iterations {
             let mut to_delete = Vec::with_capacity(iterations as usize);
 
             let mut m = 2u64;
 
-            for i in 0..10_000 {
+            for i in 0..batch {
                 let node = list.push_back(i);
                 if i % m != 0 {
                     continue;
@@ -152,10 +241,14 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for Fragmentation<'x, L> {
                 }
             }
 
+            deleted += to_delete.len();
             for i in to_delete {
                 unsafe { list.delete(i) };
             }
         }
+        self.init.metrics.report("nodes deleted", deleted as f64);
+
+        self.list = Some(list);
     }
 }
 
@@ -175,7 +268,7 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for First<'x, L> {
         }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 2);
 
         let node = list.push_front(0xDA);
@@ -201,7 +294,7 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for Last<'x, L> {
         }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 2);
 
         list.push_front(5);
@@ -227,7 +320,7 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for Order<'x, L> {
         }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 2);
 
         let n3 = list.push_front(3);
@@ -268,7 +361,7 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for UseAfterDelete<'x, L> {
         }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 2);
 
         let node = list.push_front(0xDA);
@@ -276,6 +369,9 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for UseAfterDelete<'x, L> {
         // UB incoming
         let value = black_box(list.value(node.clone()));
         if let Some(v) = value {
+            if self.init.validation.is_freed(v) {
+                panic!("read freed memory");
+            }
             if *v == 0xDA {
                 panic!("read used after free value");
             }
@@ -285,7 +381,6 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for UseAfterDelete<'x, L> {
 
 // ----------------------------------------------------------------------------
 
-// TODO: add to validation scenarios
 pub struct UseAfterDeleteAndReinsert<'x, L> {
     init: ScenarioInit<'x>,
     _p: PhantomData<L>,
@@ -300,7 +395,7 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for UseAfterDeleteAndReinser
         }
     }
 
-    fn run(self) {
+    fn run(&mut self) {
         let mut list = L::new(self.init.alloc, 2);
 
         let node = list.push_front(0xDA);
@@ -309,6 +404,9 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for UseAfterDeleteAndReinser
         // UB incoming
         let value = list.value(node.clone());
         if let Some(v) = value {
+            if self.init.validation.is_freed(v) {
+                panic!("read freed memory");
+            }
             if *v == 0xDD {
                 panic!("read used after reinsert with old node");
             }
@@ -322,27 +420,34 @@ pub struct SearchMiddle<L> {
     list: L,
     iterations: u64,
     batch: u64,
+    metrics: &'static RawMetrics,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SearchMiddle<L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[
+        RawParam::u64("size", 10_000_000, 2, 1_000_000_000),
+        RawParam::u64("batch", 100_000, 2, 1_000_000_000),
+    ];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        let iterations = init.percent_u64(10_000_000);
-        let batch = init.percent_u64(100_000);
+        let size = init.u64("size");
+        let iterations = init.scaled_u64("size");
+        let batch = init.scaled_u64("batch");
 
         let mut list = L::new(init.alloc, iterations as usize);
-        for i in 1..=10_000_000 {
+        for i in 1..=size {
             list.push_back(i);
         }
         Self {
             list,
             iterations,
             batch,
+            metrics: init.metrics,
         }
     }
 
-    fn run(self) {
-        let list = self.list;
+    fn run(&mut self) {
+        let list = &self.list;
 
         for i in 1..self.iterations / self.batch {
             let to_find = i * self.batch;
@@ -356,6 +461,8 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SearchMiddle<L> {
             );
             assert_eq!(list.value(list.next(node).unwrap()), Some(&(to_find + 1)));
         }
+        let searches = (self.iterations / self.batch).saturating_sub(1);
+        self.metrics.report("searches", searches as f64);
     }
 }
 
@@ -363,26 +470,26 @@ impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for SearchMiddle<L> {
 
 pub struct AddFrontBack<'x, L> {
     init: ScenarioInit<'x>,
-    _p: PhantomData<L>,
+    list: Option<L>,
 }
 impl<'x, L: DoubleLinkedList<'x, u64>> Scenario<'x> for AddFrontBack<'x, L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("iterations", 1_000_000, 1, 1_000_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        Self {
-            init,
-            _p: PhantomData,
-        }
+        Self { init, list: None }
     }
 
-    fn run(self) {
-        let iterations = self.init.percent_usize(1_000_000);
+    fn run(&mut self) {
+        let iterations = self.init.scaled_u64("iterations") as usize;
         let mut list = L::new(self.init.alloc, iterations);
 
         for i in 0..iterations {
             list.push_back(i as u64);
             list.push_front(i as u64);
         }
+
+        self.list = Some(list);
     }
 }
 
@@ -396,21 +503,19 @@ pub struct Page {
 
 pub struct PushPages<'x, L> {
     init: ScenarioInit<'x>,
-    _p: PhantomData<L>,
+    list: Option<L>,
 }
 impl<'x, L: DoubleLinkedList<'x, Page>> Scenario<'x> for PushPages<'x, L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("pages", 1_000, 1, 1_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        Self {
-            init,
-            _p: PhantomData,
-        }
+        Self { init, list: None }
     }
 
-    fn run(self) {
-        let init = self.init;
-        let iterations = init.percent_u64(1_000);
+    fn run(&mut self) {
+        let init = &self.init;
+        let iterations = init.scaled_u64("pages");
         let mut list = L::new(init.alloc, iterations as usize);
         let page = Page {
             data: array::from_fn(|x| x as u8),
@@ -420,6 +525,8 @@ impl<'x, L: DoubleLinkedList<'x, Page>> Scenario<'x> for PushPages<'x, L> {
             list.push_back(page.clone());
             list.push_front(page.clone());
         }
+
+        self.list = Some(list);
     }
 }
 
@@ -430,9 +537,10 @@ pub struct IteratePages<L> {
 }
 impl<'x, L: DoubleLinkedList<'x, Page>> Scenario<'x> for IteratePages<L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("pages", 1_000, 1, 1_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        let iterations = init.percent_u64(1_000);
+        let iterations = init.scaled_u64("pages");
         let mut list = L::new(init.alloc, iterations as usize);
         let page = Page {
             data: array::from_fn(|x| x as u8),
@@ -450,8 +558,8 @@ impl<'x, L: DoubleLinkedList<'x, Page>> Scenario<'x> for IteratePages<L> {
         }
     }
 
-    fn run(self) {
-        let list = self.list;
+    fn run(&mut self) {
+        let list = &self.list;
 
         let mut first = list.first();
         while let Some(element) = first {
@@ -468,13 +576,14 @@ impl<'x, L: DoubleLinkedList<'x, Page>> Scenario<'x> for IteratePages<L> {
 pub struct FindString<L> {
     list: L,
     iterations: u64,
-    _p: PhantomData<L>,
+    metrics: &'static RawMetrics,
 }
 impl<'x, L: DoubleLinkedList<'x, String>> Scenario<'x> for FindString<L> {
     type Impl = L;
+    const PARAMS: &'static [RawParam] = &[RawParam::u64("strings", 10_000, 1, 1_000_000)];
 
     fn new(init: ScenarioInit<'x>) -> Self {
-        let iterations = init.percent_u64(10_000);
+        let iterations = init.scaled_u64("strings");
         let mut list = L::new(init.alloc, iterations as usize);
         let mut s = String::with_capacity(4096);
         for _ in 0..iterations {
@@ -485,12 +594,12 @@ impl<'x, L: DoubleLinkedList<'x, String>> Scenario<'x> for FindString<L> {
         Self {
             list,
             iterations,
-            _p: PhantomData,
+            metrics: init.metrics,
         }
     }
 
-    fn run(self) {
-        let list = self.list;
+    fn run(&mut self) {
+        let list = &self.list;
 
         let mut s = String::with_capacity(4096);
         for _ in 0..self.iterations {
@@ -498,6 +607,7 @@ impl<'x, L: DoubleLinkedList<'x, String>> Scenario<'x> for FindString<L> {
 
             list.search(|x| *x == s).expect("String should be found");
         }
+        self.metrics.report("searches", self.iterations as f64);
     }
 }
 
@@ -13,12 +13,15 @@ pub struct Node<T> {
 
 impl<T> Drop for Node<T> {
     fn drop(&mut self) {
-        loop {
-            let Some(next) = self.next.take() else {
+        // Drops the rest of the list in a loop instead of recursively, but
+        // stops at the first node that is still referenced elsewhere: a
+        // deleted node must not take its successors with it.
+        let mut next = self.next.take();
+        while let Some(node) = next {
+            if Rc::strong_count(&node) > 1 {
                 return;
-            };
-            let next = next.borrow_mut().next.take();
-            self.next = next;
+            }
+            next = node.borrow_mut().next.take();
         }
     }
 }
@@ -0,0 +1,68 @@
+#pragma once
+
+// The C++ version of `Rng` in tests_api/src/rng.rs; both produce the same
+// numbers for the same seed.
+
+#include <cstddef>
+#include <cstdint>
+#include <utility>
+
+class Rng {
+    uint64_t state;
+
+    // The high half of `a * b`, without relying on a 128 bit type.
+    static constexpr uint64_t mul_high(uint64_t a, uint64_t b) {
+        uint64_t a_lo = a & 0xFFFF'FFFF, a_hi = a >> 32;
+        uint64_t b_lo = b & 0xFFFF'FFFF, b_hi = b >> 32;
+        uint64_t lo_lo = a_lo * b_lo;
+        uint64_t hi_lo = a_hi * b_lo;
+        uint64_t lo_hi = a_lo * b_hi;
+        uint64_t hi_hi = a_hi * b_hi;
+        uint64_t cross = (lo_lo >> 32) + (hi_lo & 0xFFFF'FFFF) + lo_hi;
+        return hi_hi + (hi_lo >> 32) + (cross >> 32);
+    }
+
+  public:
+    constexpr explicit Rng(uint64_t seed) : state(seed) {
+    }
+
+    constexpr uint64_t next_u64() {
+        state += 0x9E37'79B9'7F4A'7C15;
+        uint64_t z = state;
+        z = (z ^ (z >> 30)) * 0xBF58'476D'1CE4'E5B9;
+        z = (z ^ (z >> 27)) * 0x94D0'49BB'1331'11EB;
+        return z ^ (z >> 31);
+    }
+
+    // Uniform in `[0, n)`; `n` must not be 0.
+    constexpr uint64_t below(uint64_t n) {
+        return mul_high(next_u64(), n);
+    }
+
+    // Uniform in `[0, 1)`.
+    constexpr double next_f64() {
+        return static_cast<double>(next_u64() >> 11) / static_cast<double>(uint64_t(1) << 53);
+    }
+
+    // Fisher-Yates, from the back.
+    template <typename T>
+    void shuffle(T* items, size_t size) {
+        for (size_t i = size; i > 1; --i) {
+            size_t j = static_cast<size_t>(below(i));
+            std::swap(items[i - 1], items[j]);
+        }
+    }
+};
+
+// Known answers, the same as in the test of tests_api/src/rng.rs: a change on
+// either side breaks the build instead of making results incomparable.
+static_assert(
+    [] {
+        Rng rng(42);
+        bool ok = rng.next_u64() == 0xBDD7'3226'2FEB'6E95 &&
+                  rng.next_u64() == 0x28EF'E333'B266'F103 &&
+                  rng.next_u64() == 0x4752'6757'130F'9F52;
+        ok = ok && rng.below(1000) == 344 && rng.below(1000) == 38 && rng.below(1000) == 868;
+        return ok && Rng(0).next_u64() == 0xE220'A839'7B1D'CDAF;
+    }(),
+    "Rng differs from tests_api/src/rng.rs");
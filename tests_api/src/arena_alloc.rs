@@ -1,13 +1,21 @@
 use std::{
     alloc::{AllocError, Allocator, Layout},
-    cell::Cell,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, VecDeque},
     ptr::NonNull,
     slice,
 };
 
 const INIT_BYTE: u8 = 0xCD;
+/// What freed blocks are filled with when poisoning.
+const FREED_BYTE: u8 = 0xDB;
 const PAGE_SIZE: usize = 4096;
 const ALIGN: usize = 16;
+/// Sizes up to this get a size class per multiple of `ALIGN`, bigger ones a
+/// size class per power of two.
+const SMALL_MAX: usize = 1024;
+const SMALL_CLASSES: usize = SMALL_MAX / ALIGN;
+const CLASSES: usize = SMALL_CLASSES + (usize::BITS - SMALL_MAX.trailing_zeros()) as usize;
 
 pub struct Stats {
     pub no_allocs: usize,
@@ -18,10 +26,48 @@ pub struct Stats {
 #[derive(Clone, Copy)]
 struct Page([u8; PAGE_SIZE]);
 
+struct Poisoning {
+    /// Sizes of the freed blocks by address. A block leaves when it is handed
+    /// out again.
+    freed: RefCell<BTreeMap<usize, usize>>,
+    /// Freed blocks that may not be reused yet, oldest first.
+    quarantine: RefCell<VecDeque<(NonNull<u8>, usize)>>,
+    quarantine_size: Cell<usize>,
+    quarantine_cap: usize,
+    /// The free-list links of the freed blocks by address, kept here so that
+    /// the blocks stay filled with `FREED_BYTE` while on a free list.
+    links: RefCell<BTreeMap<usize, Option<NonNull<u8>>>>,
+}
+
+impl Poisoning {
+    fn new(quarantine_cap: usize) -> Poisoning {
+        Poisoning {
+            freed: RefCell::new(BTreeMap::new()),
+            quarantine: RefCell::new(VecDeque::new()),
+            quarantine_size: Cell::new(0),
+            quarantine_cap,
+            links: RefCell::new(BTreeMap::new()),
+        }
+    }
+}
+
+/// Hands out memory from one buffer allocated upfront, so the addresses are
+/// the same from run to run. By default freed memory is never reused; with
+/// [`ArenaAlloc::with_reuse`] freed blocks go to a free list of their size
+/// class and are handed out again, last freed first.
+///
+/// [`ArenaAlloc::with_poisoning`] is for validation: freed blocks are filled
+/// with `FREED_BYTE`, are kept out of reuse for a while and
+/// [`ArenaAlloc::is_freed`] tells whether memory is freed.
 pub struct ArenaAlloc {
     buffer: Vec<Page>,
     offset: Cell<usize>,
     capacity: usize,
+    /// Heads of the free lists, one per size class; every free block stores
+    /// the next one in its first bytes, or in `Poisoning::links` when
+    /// poisoning. Empty if reuse is off.
+    free_lists: Vec<Cell<Option<NonNull<u8>>>>,
+    poisoning: Option<Poisoning>,
     no_allocs: Cell<usize>,
     current_allocated: Cell<usize>,
     max_allocated: Cell<usize>,
@@ -29,6 +75,14 @@ pub struct ArenaAlloc {
 
 impl ArenaAlloc {
     pub fn new(cap: usize) -> ArenaAlloc {
+        ArenaAlloc::create(cap, false)
+    }
+
+    pub fn with_reuse(cap: usize) -> ArenaAlloc {
+        ArenaAlloc::create(cap, true)
+    }
+
+    fn create(cap: usize, reuse: bool) -> ArenaAlloc {
         if cap % PAGE_SIZE != 0 {
             panic!("capacity is not aligned to 4096");
         }
@@ -37,16 +91,111 @@ impl ArenaAlloc {
             buffer: vec![Page([INIT_BYTE; PAGE_SIZE]); cap / PAGE_SIZE],
             offset: Cell::new(0),
             capacity: cap,
+            free_lists: ArenaAlloc::free_lists(reuse),
+            poisoning: None,
             no_allocs: Cell::new(0),
             current_allocated: Cell::new(0),
             max_allocated: Cell::new(0),
         }
     }
 
+    /// Poisons freed blocks and, when reusing, keeps up to `quarantine` bytes
+    /// of them from being reused, so that a stale pointer doesn't read a new
+    /// block right away.
+    pub fn with_poisoning(mut self, quarantine: usize) -> ArenaAlloc {
+        self.poisoning = Some(Poisoning::new(quarantine));
+        self
+    }
+
+    /// Whether any of the `size` bytes at `ptr` are in a freed block. Always
+    /// `false` without poisoning.
+    pub fn is_freed(&self, ptr: *const u8, size: usize) -> bool {
+        let Some(poisoning) = &self.poisoning else {
+            return false;
+        };
+        let start = ptr as usize;
+        let end = start + size.max(1);
+        // Blocks don't overlap, so only the last one starting before `end`
+        // can reach into the range.
+        let freed = poisoning.freed.borrow();
+        match freed.range(..end).next_back() {
+            Some((block, block_size)) => block + block_size > start,
+            None => false,
+        }
+    }
+
+    fn free_lists(reuse: bool) -> Vec<Cell<Option<NonNull<u8>>>> {
+        let count = if reuse { CLASSES } else { 0 };
+        (0..count).map(|_| Cell::new(None)).collect()
+    }
+
     fn buffer(&self) -> *mut u8 {
         self.buffer.as_ptr() as *mut u8
     }
 
+    fn is_reusing(&self) -> bool {
+        !self.free_lists.is_empty()
+    }
+
+    /// The size class of a block of `size` bytes, which must be a non zero
+    /// multiple of `ALIGN`, and the size of the blocks in it.
+    fn size_class(size: usize) -> (usize, usize) {
+        if size <= SMALL_MAX {
+            (size / ALIGN - 1, size)
+        } else {
+            let size = size.next_power_of_two();
+            let index =
+                SMALL_CLASSES + (size.trailing_zeros() - SMALL_MAX.trailing_zeros()) as usize;
+            (index - 1, size)
+        }
+    }
+
+    /// The size of the block handed out for `layout`.
+    fn block_size(&self, layout: Layout) -> usize {
+        let size = layout.align_to(ALIGN).unwrap().pad_to_align().size();
+        if self.is_reusing() {
+            ArenaAlloc::size_class(size.max(ALIGN)).1
+        } else {
+            size
+        }
+    }
+
+    unsafe fn push_free(&self, block: NonNull<u8>, size: usize) {
+        let (index, _) = ArenaAlloc::size_class(size);
+        let head = &self.free_lists[index];
+        match &self.poisoning {
+            Some(poisoning) => {
+                let mut links = poisoning.links.borrow_mut();
+                links.insert(block.as_ptr() as usize, head.get());
+            }
+            None => block.cast::<Option<NonNull<u8>>>().write(head.get()),
+        }
+        head.set(Some(block));
+    }
+
+    /// The block after `block` on its free list.
+    unsafe fn next_free(&self, block: NonNull<u8>) -> Option<NonNull<u8>> {
+        match &self.poisoning {
+            Some(poisoning) => {
+                let mut links = poisoning.links.borrow_mut();
+                links.remove(&(block.as_ptr() as usize)).unwrap()
+            }
+            None => block.cast::<Option<NonNull<u8>>>().read(),
+        }
+    }
+
+    /// Takes `size` bytes aligned to `align` from the end of the used part
+    /// of the buffer; fails when the buffer is full.
+    fn bump(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        let buffer = self.buffer() as usize;
+        let start = (buffer + self.offset.get()).next_multiple_of(align) - buffer;
+        if start + size > self.capacity {
+            return Err(AllocError);
+        }
+        self.offset.set(start + size);
+        Ok(unsafe { self.buffer().add(start) })
+    }
+
     pub fn reset(&mut self) {
         let buffer = self.buffer();
         let slice = unsafe { slice::from_raw_parts_mut(buffer, self.capacity) };
@@ -56,6 +205,11 @@ impl ArenaAlloc {
             buffer: std::mem::take(&mut self.buffer),
             offset: Cell::new(0),
             capacity: self.capacity,
+            free_lists: ArenaAlloc::free_lists(self.is_reusing()),
+            poisoning: self
+                .poisoning
+                .as_ref()
+                .map(|x| Poisoning::new(x.quarantine_cap)),
             no_allocs: Cell::new(0),
             current_allocated: Cell::new(0),
             max_allocated: Cell::new(0),
@@ -71,7 +225,36 @@ impl ArenaAlloc {
 }
 unsafe impl Allocator for ArenaAlloc {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        // TODO: use handle_alloc_error instead of panic
+        let align = layout.align().max(ALIGN);
+        let mut size = layout
+            .align_to(ALIGN)
+            .map_err(|_| AllocError)?
+            .pad_to_align()
+            .size();
+        let ptr = if self.is_reusing() {
+            let (index, class_size) = ArenaAlloc::size_class(size.max(ALIGN));
+            size = class_size;
+            let head = &self.free_lists[index];
+            // Blocks of a class can come from allocations with a smaller
+            // alignment; rather than searching the list, take a new one.
+            let is_aligned = |x: &NonNull<u8>| (x.as_ptr() as usize).is_multiple_of(align);
+            match head.get().filter(is_aligned) {
+                Some(block) => {
+                    head.set(unsafe { self.next_free(block) });
+                    if let Some(poisoning) = &self.poisoning {
+                        poisoning
+                            .freed
+                            .borrow_mut()
+                            .remove(&(block.as_ptr() as usize));
+                        unsafe { block.as_ptr().write_bytes(INIT_BYTE, size) };
+                    }
+                    block.as_ptr()
+                }
+                None => self.bump(size, align)?,
+            }
+        } else {
+            self.bump(size, align)?
+        };
 
         self.no_allocs.set(self.no_allocs.get() + 1);
         self.current_allocated
@@ -79,27 +262,166 @@ unsafe impl Allocator for ArenaAlloc {
         self.max_allocated
             .set(self.max_allocated.get().max(self.current_allocated.get()));
 
-        if layout.align() > ALIGN {
-            panic!("align not supported");
-        }
-        let layout = layout
-            .align_to(ALIGN)
-            .expect("align_to failed")
-            .pad_to_align();
-        let size = layout.size();
-        let offset = self.offset.get();
-        if offset + size > self.capacity {
-            panic!("space exhausted");
-        }
-        let ptr = unsafe { self.buffer().add(offset) };
-        self.offset.set(offset + size);
-
         let nonnull = unsafe { NonNull::new_unchecked(ptr) };
         Ok(NonNull::slice_from_raw_parts(nonnull, size))
     }
 
-    unsafe fn deallocate(&self, _ptr: NonNull<u8>, layout: Layout) {
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
         self.current_allocated
             .set(self.current_allocated.get() - layout.size());
+
+        let size = self.block_size(layout);
+        let Some(poisoning) = &self.poisoning else {
+            if self.is_reusing() {
+                self.push_free(ptr, size);
+            }
+            return;
+        };
+        // Without reuse, a zero sized block shares its address with the next.
+        if size == 0 {
+            return;
+        }
+
+        ptr.as_ptr().write_bytes(FREED_BYTE, size);
+        poisoning
+            .freed
+            .borrow_mut()
+            .insert(ptr.as_ptr() as usize, size);
+        if !self.is_reusing() {
+            return;
+        }
+        let mut quarantine = poisoning.quarantine.borrow_mut();
+        quarantine.push_back((ptr, size));
+        poisoning
+            .quarantine_size
+            .set(poisoning.quarantine_size.get() + size);
+        while poisoning.quarantine_size.get() > poisoning.quarantine_cap {
+            let (block, block_size) = quarantine.pop_front().unwrap();
+            poisoning
+                .quarantine_size
+                .set(poisoning.quarantine_size.get() - block_size);
+            self.push_free(block, block_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 8).unwrap()
+    }
+
+    fn alloc(arena: &ArenaAlloc, size: usize) -> NonNull<u8> {
+        arena.allocate(layout(size)).unwrap().cast()
+    }
+
+    #[test]
+    fn reuse_is_last_freed_first() {
+        let arena = ArenaAlloc::with_reuse(PAGE_SIZE);
+        let a = alloc(&arena, 32);
+        let b = alloc(&arena, 32);
+        unsafe {
+            arena.deallocate(a, layout(32));
+            arena.deallocate(b, layout(32));
+        }
+        assert_eq!(alloc(&arena, 32), b);
+        assert_eq!(alloc(&arena, 32), a);
+        let c = alloc(&arena, 32);
+        assert!(c != a && c != b);
+
+        let arena = ArenaAlloc::new(PAGE_SIZE);
+        let a = alloc(&arena, 32);
+        unsafe { arena.deallocate(a, layout(32)) };
+        assert_ne!(alloc(&arena, 32), a);
+    }
+
+    #[test]
+    fn sizes_round_up_to_their_class() {
+        let arena = ArenaAlloc::with_reuse(4 * PAGE_SIZE);
+        assert_eq!(arena.allocate(layout(0)).unwrap().len(), 16);
+        assert_eq!(arena.allocate(layout(20)).unwrap().len(), 32);
+        assert_eq!(arena.allocate(layout(1024)).unwrap().len(), 1024);
+        assert_eq!(arena.allocate(layout(1100)).unwrap().len(), 2048);
+
+        // A block is reused for any size of its class, and only for those.
+        let a = alloc(&arena, 20);
+        unsafe { arena.deallocate(a, layout(20)) };
+        assert_ne!(alloc(&arena, 40), a);
+        assert_eq!(alloc(&arena, 30), a);
+    }
+
+    #[test]
+    fn freed_blocks_are_poisoned() {
+        let arena = ArenaAlloc::new(PAGE_SIZE).with_poisoning(0);
+        let a = alloc(&arena, 64);
+        unsafe {
+            a.as_ptr().write_bytes(0, 64);
+            arena.deallocate(a, layout(64));
+        }
+        let bytes = unsafe { slice::from_raw_parts(a.as_ptr(), 64) };
+        assert!(bytes.iter().all(|&x| x == FREED_BYTE));
+
+        let a = a.as_ptr();
+        assert!(arena.is_freed(a, 64));
+        assert!(arena.is_freed(a.wrapping_add(63), 1));
+        assert!(arena.is_freed(a.wrapping_sub(8), 16));
+        assert!(!arena.is_freed(a.wrapping_sub(8), 8));
+        assert!(!arena.is_freed(a.wrapping_add(64), 8));
+        assert!(!ArenaAlloc::new(PAGE_SIZE).is_freed(a, 64));
+    }
+
+    #[test]
+    fn quarantine_delays_reuse() {
+        let arena = ArenaAlloc::with_reuse(PAGE_SIZE).with_poisoning(64);
+        let a = alloc(&arena, 64);
+        unsafe { arena.deallocate(a, layout(64)) };
+        let b = alloc(&arena, 64);
+        assert_ne!(b, a);
+
+        // Freeing `b` pushes `a` out of the quarantine; it stays freed until
+        // it is handed out again.
+        unsafe { arena.deallocate(b, layout(64)) };
+        assert!(arena.is_freed(a.as_ptr(), 64));
+        let bytes = unsafe { slice::from_raw_parts(a.as_ptr(), 64) };
+        assert!(bytes.iter().all(|&x| x == FREED_BYTE));
+        assert_eq!(alloc(&arena, 64), a);
+        assert!(!arena.is_freed(a.as_ptr(), 64));
+        assert!(arena.is_freed(b.as_ptr(), 64));
+        let bytes = unsafe { slice::from_raw_parts(a.as_ptr(), 64) };
+        assert!(bytes.iter().all(|&x| x == INIT_BYTE));
+    }
+
+    #[test]
+    fn out_of_memory_is_an_error() {
+        let mut arena = ArenaAlloc::new(PAGE_SIZE);
+        assert!(arena.allocate(layout(2 * PAGE_SIZE)).is_err());
+        alloc(&arena, PAGE_SIZE);
+        assert!(arena.allocate(layout(1)).is_err());
+        assert_eq!(arena.stats().no_allocs, 1);
+        assert_eq!(arena.stats().max_allocated, PAGE_SIZE);
+
+        arena.reset();
+        assert!(arena.allocate(layout(PAGE_SIZE)).is_ok());
+    }
+
+    #[test]
+    fn misaligned_free_blocks_are_skipped() {
+        let arena = ArenaAlloc::with_reuse(4 * PAGE_SIZE);
+        alloc(&arena, 16);
+        let a = alloc(&arena, 32);
+        assert_eq!(a.as_ptr() as usize % 32, 16);
+        unsafe { arena.deallocate(a, layout(32)) };
+
+        let aligned = Layout::from_size_align(32, 32).unwrap();
+        let b = arena.allocate(aligned).unwrap().cast::<u8>();
+        assert_ne!(b, a);
+        assert!((b.as_ptr() as usize).is_multiple_of(32));
+        assert_eq!(alloc(&arena, 32), a);
+
+        let aligned = Layout::from_size_align(16, PAGE_SIZE).unwrap();
+        let c = arena.allocate(aligned).unwrap().cast::<u8>();
+        assert!((c.as_ptr() as usize).is_multiple_of(PAGE_SIZE));
     }
 }
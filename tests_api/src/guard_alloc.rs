@@ -0,0 +1,152 @@
+use std::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::NonNull,
+};
+
+/// Puts every allocation in its own mapping, flush against an inaccessible
+/// guard page, electric fence style. Reading past the end of a block faults,
+/// and so does any access after it is freed, as freed mappings are protected
+/// instead of unmapped so that their addresses are never handed out again.
+///
+/// Every allocation costs at least two pages and a mapping, so this is only
+/// meant for validation scenarios.
+pub struct GuardAlloc {
+    page_size: usize,
+}
+
+impl GuardAlloc {
+    pub fn new() -> GuardAlloc {
+        GuardAlloc {
+            page_size: sys::page_size(),
+        }
+    }
+
+    /// The number of pages before the guard page for `layout`.
+    fn data_pages(&self, layout: Layout) -> usize {
+        layout.size().div_ceil(self.page_size).max(1)
+    }
+}
+
+impl Default for GuardAlloc {
+    fn default() -> Self {
+        GuardAlloc::new()
+    }
+}
+
+unsafe impl Allocator for GuardAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.align() > self.page_size {
+            return Err(AllocError);
+        }
+        let data_size = self.data_pages(layout) * self.page_size;
+        let base = sys::map(data_size + self.page_size).ok_or(AllocError)?;
+        let guard = unsafe { base.add(data_size) };
+        if !sys::protect(guard, self.page_size) {
+            unsafe { sys::unmap(base, data_size + self.page_size) };
+            return Err(AllocError);
+        }
+
+        // As close to the guard page as the alignment allows.
+        let offset = (data_size - layout.size()) & !(layout.align() - 1);
+        let ptr = unsafe { NonNull::new_unchecked(base.add(offset)) };
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // The block ends less than an alignment before the guard page.
+        let end = ptr.as_ptr() as usize + layout.size();
+        let guard = end.next_multiple_of(self.page_size);
+        let data_size = self.data_pages(layout) * self.page_size;
+        let base = (guard - data_size) as *mut u8;
+        // The guard page is protected already.
+        if !sys::protect(base, data_size) {
+            panic!("couldn't protect a freed block");
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ptr;
+
+    pub fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    /// A new read write mapping of `size` bytes.
+    pub fn map(size: usize) -> Option<*mut u8> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        (ptr != libc::MAP_FAILED).then_some(ptr as *mut u8)
+    }
+
+    /// Makes `size` bytes at `ptr` inaccessible.
+    pub fn protect(ptr: *mut u8, size: usize) -> bool {
+        unsafe { libc::mprotect(ptr as *mut libc::c_void, size, libc::PROT_NONE) == 0 }
+    }
+
+    pub unsafe fn unmap(ptr: *mut u8, size: usize) {
+        libc::munmap(ptr as *mut libc::c_void, size);
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    pub fn page_size() -> usize {
+        4096
+    }
+
+    pub fn map(_size: usize) -> Option<*mut u8> {
+        None
+    }
+
+    pub fn protect(_ptr: *mut u8, _size: usize) -> bool {
+        false
+    }
+
+    pub unsafe fn unmap(_ptr: *mut u8, _size: usize) {}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_end_at_the_guard_page() {
+        let guard = GuardAlloc::new();
+        let page = guard.page_size;
+        for size in [0, 1, 100, page, page + 1] {
+            let layout = Layout::from_size_align(size, 1).unwrap();
+            let ptr = guard.allocate(layout).unwrap().cast::<u8>();
+            unsafe { ptr.as_ptr().write_bytes(0, size) };
+            let end = ptr.as_ptr() as usize + size;
+            assert!(end.is_multiple_of(page));
+            unsafe { guard.deallocate(ptr, layout) };
+        }
+    }
+
+    #[test]
+    fn alignment_is_respected() {
+        let guard = GuardAlloc::new();
+        let page = guard.page_size;
+        for align in [8, 64, page] {
+            let layout = Layout::from_size_align(100, align).unwrap();
+            let ptr = guard.allocate(layout).unwrap().cast::<u8>();
+            let end = ptr.as_ptr() as usize + layout.size();
+            assert!((ptr.as_ptr() as usize).is_multiple_of(align));
+            assert!(end.next_multiple_of(page) - end < align);
+            unsafe { guard.deallocate(ptr, layout) };
+        }
+
+        let layout = Layout::from_size_align(100, 2 * page).unwrap();
+        assert!(guard.allocate(layout).is_err());
+    }
+}
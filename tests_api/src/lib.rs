@@ -1,23 +1,44 @@
 #![feature(allocator_api)]
 
 pub mod arena_alloc;
+pub mod guard_alloc;
+pub mod metrics;
+pub mod params;
+pub mod raw_alloc;
+pub mod rng;
 pub mod snalloc;
 pub mod stats_alloc;
+pub mod validation;
 
-use std::{alloc::Allocator, ffi::c_void};
+use metrics::RawMetrics;
+use params::{RawParam, RawParamValue};
+use raw_alloc::RawAllocator;
+use std::{alloc::Allocator, ffi::c_void, mem::size_of};
+use validation::RawValidation;
 
 pub type Handle = *mut c_void;
 
 #[repr(C)]
 pub struct RawScenarioInit {
-    pub alloc: *const *const dyn Allocator,
+    pub alloc: *const RawAllocator,
     pub percent: u32,
+    /// Valid until the scenario is dropped.
+    pub metrics: *const RawMetrics,
+    /// One value per entry of `RawScenario::params`, in the same order.
+    /// Valid until the scenario is dropped.
+    pub params: *const RawParamValue,
+    /// Seeds the randomness of the scenario, see `rng`.
+    pub seed: u64,
+    /// Valid until the scenario is dropped.
+    pub validation: *const RawValidation,
 }
 
 pub type FnScenarioNew = unsafe extern "C" fn(init: RawScenarioInit) -> Handle;
 pub type FnScenarioRun = unsafe extern "C" fn(handle: Handle);
+pub type FnScenarioDrop = unsafe extern "C" fn(handle: Handle);
 
 #[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum RawScenarioKind {
     Bench,
     Validation,
@@ -28,10 +49,17 @@ pub struct RawScenario {
     pub name: *const u8,
     pub name_size: usize,
 
+    /// Setup: builds the scenario, e.g. the list a search runs over.
     pub new: FnScenarioNew,
+    /// The measured part; called once per handle.
     pub run: FnScenarioRun,
+    /// Teardown: frees the handle and everything `run` left behind.
+    pub drop: FnScenarioDrop,
 
     pub kind: RawScenarioKind,
+
+    pub params: *const RawParam,
+    pub params_count: usize,
 }
 
 #[repr(C)]
@@ -43,15 +71,44 @@ pub struct RawImpl {
     pub scenarios_count: usize,
 }
 
+/// First field of every `RawLoadResult`.
+pub const API_MAGIC: u32 = 0x4C4C_4444;
+/// Must be bumped on every change to the layout of the `Raw*` types or to the
+/// meaning of their fields.
+pub const API_VERSION: u32 = 6;
+
 #[repr(C)]
 pub struct RawLoadResult {
+    /// Always `API_MAGIC`.
+    pub magic: u32,
+    /// The `API_VERSION` the library was built against.
+    pub version: u32,
+    /// `size_of::<RawLoadResult>()` as seen by the library.
+    pub size: usize,
+
     pub list_impl: *const RawImpl,
     pub list_impl_count: usize,
 }
 
+impl RawLoadResult {
+    pub const fn new(list_impl: &'static [RawImpl]) -> RawLoadResult {
+        RawLoadResult {
+            magic: API_MAGIC,
+            version: API_VERSION,
+            size: size_of::<RawLoadResult>(),
+            list_impl: list_impl.as_ptr(),
+            list_impl_count: list_impl.len(),
+        }
+    }
+}
+
 pub type FnLoadTests = unsafe extern "C" fn() -> RawLoadResult;
+/// Exported as `api_version` next to `load_tests`. The runner checks it before
+/// calling `load_tests`, whose return value can't be read safely if the layout
+/// of `RawLoadResult` changed.
+pub type FnApiVersion = unsafe extern "C" fn() -> u32;
 
 #[no_mangle]
-pub extern "C" fn ignore_this_cbindgen_needs_to_find_stuff(_: FnLoadTests) {}
+pub extern "C" fn ignore_this_cbindgen_needs_to_find_stuff(_: FnLoadTests, _: FnApiVersion) {}
 
 pub type TheAlloc = dyn Allocator;
@@ -0,0 +1,38 @@
+use std::{ffi::c_void, slice};
+
+pub type FnMetric =
+    unsafe extern "C" fn(ctx: *const c_void, name: *const u8, name_size: usize, value: f64);
+
+/// Where a scenario reports its own measurements, e.g. a checksum or the
+/// number of nodes it visited. `name` is UTF-8; reporting a name again
+/// replaces the previous value.
+#[repr(C)]
+pub struct RawMetrics {
+    pub ctx: *const c_void,
+    pub report: FnMetric,
+}
+
+impl RawMetrics {
+    /// Calls `f` for every metric. `f` must outlive the result.
+    pub fn new<F: Fn(&str, f64)>(f: &F) -> RawMetrics {
+        RawMetrics {
+            ctx: f as *const F as *const c_void,
+            report: raw_report::<F>,
+        }
+    }
+
+    pub fn report(&self, name: &str, value: f64) {
+        unsafe { (self.report)(self.ctx, name.as_ptr(), name.len(), value) }
+    }
+}
+
+unsafe extern "C" fn raw_report<F: Fn(&str, f64)>(
+    ctx: *const c_void,
+    name: *const u8,
+    name_size: usize,
+    value: f64,
+) {
+    let f = &*(ctx as *const F);
+    let name = slice::from_raw_parts(name, name_size);
+    f(&String::from_utf8_lossy(name), value);
+}
@@ -0,0 +1,128 @@
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RawParamKind {
+    U64,
+    F64,
+}
+
+/// A parameter value; `kind` of the [`RawParam`] says which field is valid.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union RawParamValue {
+    pub u64: u64,
+    pub f64: f64,
+}
+
+/// A named knob of a scenario, e.g. the number of nodes it pushes. The runner
+/// passes the default unless it is overridden, and checks overrides against
+/// `min..=max`.
+#[repr(C)]
+pub struct RawParam {
+    pub name: *const u8,
+    pub name_size: usize,
+
+    pub kind: RawParamKind,
+    pub default_value: RawParamValue,
+    pub min: RawParamValue,
+    pub max: RawParamValue,
+}
+
+impl RawParam {
+    pub const fn u64(name: &'static str, default_value: u64, min: u64, max: u64) -> RawParam {
+        RawParam {
+            name: name.as_ptr(),
+            name_size: name.len(),
+            kind: RawParamKind::U64,
+            default_value: RawParamValue { u64: default_value },
+            min: RawParamValue { u64: min },
+            max: RawParamValue { u64: max },
+        }
+    }
+
+    pub const fn f64(name: &'static str, default_value: f64, min: f64, max: f64) -> RawParam {
+        RawParam {
+            name: name.as_ptr(),
+            name_size: name.len(),
+            kind: RawParamKind::F64,
+            default_value: RawParamValue { f64: default_value },
+            min: RawParamValue { f64: min },
+            max: RawParamValue { f64: max },
+        }
+    }
+
+    /// # Safety
+    /// `name` must be valid UTF-8, as it is for params made by [`RawParam::u64`]
+    /// and [`RawParam::f64`].
+    pub unsafe fn name(&self) -> &str {
+        let name = std::slice::from_raw_parts(self.name, self.name_size);
+        std::str::from_utf8_unchecked(name)
+    }
+
+    /// Parses `s` as a value of this parameter and checks its range.
+    pub fn parse(&self, s: &str) -> Result<RawParamValue, String> {
+        let value = match self.kind {
+            RawParamKind::U64 => RawParamValue {
+                u64: s.parse().map_err(|_| format!("`{s}` isn't an integer"))?,
+            },
+            RawParamKind::F64 => RawParamValue {
+                f64: s.parse().map_err(|_| format!("`{s}` isn't a number"))?,
+            },
+        };
+        let in_range = unsafe {
+            match self.kind {
+                RawParamKind::U64 => (self.min.u64..=self.max.u64).contains(&value.u64),
+                RawParamKind::F64 => (self.min.f64..=self.max.f64).contains(&value.f64),
+            }
+        };
+        if !in_range {
+            return Err(format!(
+                "{} is out of range {}..={}",
+                self.display(value),
+                self.display(self.min),
+                self.display(self.max)
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Formats `value` according to the kind of this parameter.
+    pub fn display(&self, value: RawParamValue) -> String {
+        match self.kind {
+            RawParamKind::U64 => unsafe { value.u64.to_string() },
+            RawParamKind::F64 => unsafe { value.f64.to_string() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_parsed_and_checked() {
+        let size = RawParam::u64("size", 10, 1, 100);
+        assert_eq!(unsafe { size.parse("1").unwrap().u64 }, 1);
+        assert_eq!(unsafe { size.parse("100").unwrap().u64 }, 100);
+        assert_eq!(size.parse("0").err().unwrap(), "0 is out of range 1..=100");
+        assert_eq!(
+            size.parse("101").err().unwrap(),
+            "101 is out of range 1..=100"
+        );
+        for i in ["", "-1", "1.5", "1e3", "ten"] {
+            assert_eq!(
+                size.parse(i).err().unwrap(),
+                format!("`{i}` isn't an integer")
+            );
+        }
+
+        let ratio = RawParam::f64("ratio", 0.5, 0.0, 1.0);
+        assert_eq!(unsafe { ratio.parse("0.25").unwrap().f64 }, 0.25);
+        assert_eq!(ratio.parse("x").err().unwrap(), "`x` isn't a number");
+        for i in ["NaN", "inf", "-0.1"] {
+            assert!(
+                ratio.parse(i).err().unwrap().contains("out of range"),
+                "{i}"
+            );
+        }
+    }
+}
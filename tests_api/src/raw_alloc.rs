@@ -0,0 +1,243 @@
+use std::{
+    alloc::{AllocError, Allocator, Layout},
+    ffi::c_void,
+    ptr::{self, NonNull},
+};
+
+pub type FnAlloc = unsafe extern "C" fn(ctx: *const c_void, size: usize, align: usize) -> *mut u8;
+pub type FnDealloc =
+    unsafe extern "C" fn(ctx: *const c_void, ptr: *mut u8, size: usize, align: usize);
+pub type FnRealloc = unsafe extern "C" fn(
+    ctx: *const c_void,
+    ptr: *mut u8,
+    old_size: usize,
+    align: usize,
+    new_size: usize,
+) -> *mut u8;
+
+/// An allocator that foreign code can call. Every function gets `ctx` as its
+/// first argument; `alloc`, `alloc_zeroed` and `realloc` return null on
+/// failure, in which case the original block of `realloc` is still valid.
+#[repr(C)]
+pub struct RawAllocator {
+    pub ctx: *const c_void,
+    pub alloc: FnAlloc,
+    pub alloc_zeroed: FnAlloc,
+    pub dealloc: FnDealloc,
+    pub realloc: FnRealloc,
+}
+
+impl RawAllocator {
+    /// Exposes `alloc` through a vtable. `alloc` must outlive the result.
+    pub fn new<A: Allocator>(alloc: &A) -> RawAllocator {
+        RawAllocator {
+            ctx: alloc as *const A as *const c_void,
+            alloc: raw_alloc::<A>,
+            alloc_zeroed: raw_alloc_zeroed::<A>,
+            dealloc: raw_dealloc::<A>,
+            realloc: raw_realloc::<A>,
+        }
+    }
+}
+
+fn to_raw(result: Result<NonNull<[u8]>, AllocError>) -> *mut u8 {
+    match result {
+        Ok(ptr) => ptr.as_ptr() as *mut u8,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn raw_alloc<A: Allocator>(
+    ctx: *const c_void,
+    size: usize,
+    align: usize,
+) -> *mut u8 {
+    let alloc = &*(ctx as *const A);
+    let Ok(layout) = Layout::from_size_align(size, align) else {
+        return ptr::null_mut();
+    };
+    to_raw(alloc.allocate(layout))
+}
+
+unsafe extern "C" fn raw_alloc_zeroed<A: Allocator>(
+    ctx: *const c_void,
+    size: usize,
+    align: usize,
+) -> *mut u8 {
+    let alloc = &*(ctx as *const A);
+    let Ok(layout) = Layout::from_size_align(size, align) else {
+        return ptr::null_mut();
+    };
+    to_raw(alloc.allocate_zeroed(layout))
+}
+
+unsafe extern "C" fn raw_dealloc<A: Allocator>(
+    ctx: *const c_void,
+    ptr: *mut u8,
+    size: usize,
+    align: usize,
+) {
+    let alloc = &*(ctx as *const A);
+    let layout = Layout::from_size_align_unchecked(size, align);
+    alloc.deallocate(NonNull::new_unchecked(ptr), layout);
+}
+
+unsafe extern "C" fn raw_realloc<A: Allocator>(
+    ctx: *const c_void,
+    ptr: *mut u8,
+    old_size: usize,
+    align: usize,
+    new_size: usize,
+) -> *mut u8 {
+    let alloc = &*(ctx as *const A);
+    let ptr = NonNull::new_unchecked(ptr);
+    let old_layout = Layout::from_size_align_unchecked(old_size, align);
+    let Ok(new_layout) = Layout::from_size_align(new_size, align) else {
+        return ptr::null_mut();
+    };
+    if new_size >= old_size {
+        to_raw(alloc.grow(ptr, old_layout, new_layout))
+    } else {
+        to_raw(alloc.shrink(ptr, old_layout, new_layout))
+    }
+}
+
+/// The Rust side of a [`RawAllocator`] handed over by foreign code.
+#[repr(transparent)]
+pub struct ForeignAllocator(RawAllocator);
+
+impl ForeignAllocator {
+    /// # Safety
+    /// `raw` must point to a valid [`RawAllocator`] that outlives `'a`.
+    pub unsafe fn from_raw<'a>(raw: *const RawAllocator) -> &'a ForeignAllocator {
+        &*(raw as *const ForeignAllocator)
+    }
+
+    fn from_ptr(ptr: *mut u8, size: usize) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = NonNull::new(ptr).ok_or(AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, size))
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        // The vtable keeps the alignment of the block, like `realloc` in C.
+        if new_layout.align() != old_layout.align() {
+            let new = self.allocate(new_layout)?;
+            let size = old_layout.size().min(new_layout.size());
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr() as *mut u8, size);
+            self.deallocate(ptr, old_layout);
+            return Ok(new);
+        }
+
+        let raw = &self.0;
+        let new = (raw.realloc)(
+            raw.ctx,
+            ptr.as_ptr(),
+            old_layout.size(),
+            old_layout.align(),
+            new_layout.size(),
+        );
+        Self::from_ptr(new, new_layout.size())
+    }
+}
+
+unsafe impl Allocator for ForeignAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let raw = &self.0;
+        let ptr = unsafe { (raw.alloc)(raw.ctx, layout.size(), layout.align()) };
+        Self::from_ptr(ptr, layout.size())
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let raw = &self.0;
+        let ptr = unsafe { (raw.alloc_zeroed)(raw.ctx, layout.size(), layout.align()) };
+        Self::from_ptr(ptr, layout.size())
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let raw = &self.0;
+        (raw.dealloc)(raw.ctx, ptr.as_ptr(), layout.size(), layout.align());
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.realloc(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new = self.realloc(ptr, old_layout, new_layout)?;
+        let tail = (new.as_ptr() as *mut u8).add(old_layout.size());
+        tail.write_bytes(0, new_layout.size() - old_layout.size());
+        Ok(new)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.realloc(ptr, old_layout, new_layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{arena_alloc::ArenaAlloc, stats_alloc::StatsAllocator};
+    use std::slice;
+
+    #[test]
+    fn round_trip_through_the_vtable() {
+        // Fresh arena memory isn't zeroed, so zeroing shows.
+        let inner = StatsAllocator::new(ArenaAlloc::new(4096));
+        let raw = RawAllocator::new(&inner);
+        let alloc = unsafe { ForeignAllocator::from_raw(&raw) };
+        let bytes = |ptr: NonNull<[u8]>| unsafe {
+            slice::from_raw_parts(ptr.cast::<u8>().as_ptr(), ptr.len())
+        };
+
+        let small = Layout::from_size_align(16, 8).unwrap();
+        let ptr = alloc.allocate_zeroed(small).unwrap();
+        assert_eq!(bytes(ptr), [0; 16]);
+        let ptr = alloc.allocate(small).unwrap().cast::<u8>();
+        unsafe { ptr.as_ptr().write_bytes(0xAA, 16) };
+
+        // Same alignment: one `realloc` each.
+        let big = Layout::from_size_align(64, 8).unwrap();
+        let ptr = unsafe { alloc.grow_zeroed(ptr, small, big) }.unwrap();
+        assert_eq!(bytes(ptr)[..16], [0xAA; 16]);
+        assert_eq!(bytes(ptr)[16..], [0; 48]);
+        let tiny = Layout::from_size_align(8, 8).unwrap();
+        let ptr = unsafe { alloc.shrink(ptr.cast(), big, tiny) }.unwrap();
+        assert_eq!(bytes(ptr), [0xAA; 8]);
+        assert_eq!(inner.no_allocs(), 2);
+        assert_eq!(inner.no_reallocs(), 2);
+
+        // Another alignment: a new block and a copy instead.
+        let aligned = Layout::from_size_align(32, 256).unwrap();
+        let ptr = unsafe { alloc.grow(ptr.cast(), tiny, aligned) }.unwrap();
+        assert!((ptr.cast::<u8>().as_ptr() as usize).is_multiple_of(256));
+        assert_eq!(bytes(ptr)[..8], [0xAA; 8]);
+        assert_eq!(inner.no_allocs(), 3);
+        assert_eq!(inner.no_reallocs(), 2);
+
+        unsafe { alloc.deallocate(ptr.cast(), aligned) };
+        assert!(alloc
+            .allocate(Layout::from_size_align(8192, 8).unwrap())
+            .is_err());
+    }
+}
@@ -10,6 +10,8 @@ pub struct StatsAllocator<T: Allocator> {
 
     time: Cell<Duration>,
     no_allocs: Cell<usize>,
+    no_reallocs: Cell<usize>,
+    bytes_copied: Cell<usize>,
     current_allocated: Cell<usize>,
     max_allocated: Cell<usize>,
 }
@@ -19,6 +21,8 @@ impl<T: Allocator> StatsAllocator<T> {
             inner: alloc,
             time: Cell::new(Duration::ZERO),
             no_allocs: Cell::new(0),
+            no_reallocs: Cell::new(0),
+            bytes_copied: Cell::new(0),
             current_allocated: Cell::new(0),
             max_allocated: Cell::new(0),
         }
@@ -30,12 +34,40 @@ impl<T: Allocator> StatsAllocator<T> {
     pub fn no_allocs(&self) -> usize {
         self.no_allocs.get()
     }
+    /// Successful `grow`s and `shrink`s; they don't count as allocations.
+    pub fn no_reallocs(&self) -> usize {
+        self.no_reallocs.get()
+    }
+    /// Bytes moved by reallocs that couldn't resize in place.
+    pub fn bytes_copied(&self) -> usize {
+        self.bytes_copied.get()
+    }
     pub fn time(&self) -> Duration {
         self.time.get()
     }
     pub fn reset_time(&self) {
         self.time.set(Duration::ZERO);
     }
+
+    fn count_realloc(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        result: Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new_ptr = result?;
+        self.no_reallocs.set(self.no_reallocs.get() + 1);
+        if new_ptr.cast::<u8>() != ptr {
+            let copied = old_layout.size().min(new_layout.size());
+            self.bytes_copied.set(self.bytes_copied.get() + copied);
+        }
+        self.current_allocated
+            .set(self.current_allocated.get() - old_layout.size() + new_layout.size());
+        self.max_allocated
+            .set(self.max_allocated.get().max(self.current_allocated.get()));
+        Ok(new_ptr)
+    }
 }
 
 fn calc_time<R, F: Fn() -> R>(time: &Cell<Duration>, f: F) -> R {
@@ -72,4 +104,113 @@ unsafe impl<T: Allocator> Allocator for StatsAllocator<T> {
 
         calc_time(&self.time, || self.inner.deallocate(ptr, layout))
     }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let result = calc_time(&self.time, || self.inner.grow(ptr, old_layout, new_layout));
+        self.count_realloc(ptr, old_layout, new_layout, result)
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let result = calc_time(&self.time, || {
+            self.inner.grow_zeroed(ptr, old_layout, new_layout)
+        });
+        self.count_realloc(ptr, old_layout, new_layout, result)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let result = calc_time(&self.time, || {
+            self.inner.shrink(ptr, old_layout, new_layout)
+        });
+        self.count_realloc(ptr, old_layout, new_layout, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena_alloc::ArenaAlloc;
+
+    /// Resizes its one block in place, up to 256 bytes.
+    struct InPlace([u8; 256]);
+
+    unsafe impl Allocator for InPlace {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            if layout.size() > 256 || layout.align() > 1 {
+                return Err(AllocError);
+            }
+            let ptr = NonNull::from(&self.0).cast::<u8>();
+            Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+        }
+
+        unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            _old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            self.allocate(new_layout)?;
+            Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            _old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+        }
+    }
+
+    fn bytes(size: usize) -> Layout {
+        Layout::array::<u8>(size).unwrap()
+    }
+
+    #[test]
+    fn reallocs_count_what_they_copy() {
+        // The arena never resizes in place.
+        let stats = StatsAllocator::new(ArenaAlloc::new(4096));
+        let ptr = stats.allocate(bytes(16)).unwrap().cast();
+        let ptr = unsafe { stats.grow(ptr, bytes(16), bytes(64)) }.unwrap();
+        unsafe { stats.shrink(ptr.cast(), bytes(64), bytes(32)) }.unwrap();
+        assert_eq!(stats.no_allocs(), 1);
+        assert_eq!(stats.no_reallocs(), 2);
+        assert_eq!(stats.bytes_copied(), 16 + 32);
+        assert_eq!(stats.max_allocated(), 64);
+
+        let stats = StatsAllocator::new(InPlace([0; 256]));
+        let ptr = stats.allocate(bytes(16)).unwrap().cast();
+        let ptr = unsafe { stats.grow(ptr, bytes(16), bytes(128)) }.unwrap();
+        unsafe { stats.shrink(ptr.cast(), bytes(128), bytes(8)) }.unwrap();
+        assert_eq!(stats.no_allocs(), 1);
+        assert_eq!(stats.no_reallocs(), 2);
+        assert_eq!(stats.bytes_copied(), 0);
+        assert_eq!(stats.max_allocated(), 128);
+    }
+
+    #[test]
+    fn failed_reallocs_are_not_counted() {
+        let stats = StatsAllocator::new(InPlace([0; 256]));
+        let ptr = stats.allocate(bytes(16)).unwrap().cast();
+        assert!(unsafe { stats.grow(ptr, bytes(16), bytes(512)) }.is_err());
+        assert_eq!(stats.no_reallocs(), 0);
+        assert_eq!(stats.max_allocated(), 16);
+    }
 }
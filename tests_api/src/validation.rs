@@ -0,0 +1,37 @@
+use std::ffi::c_void;
+
+pub type FnIsFreed = unsafe extern "C" fn(ctx: *const c_void, ptr: *const u8, size: usize) -> bool;
+
+/// Lets validation scenarios ask the allocator whether memory they are about
+/// to read was freed. Only allocators that poison freed memory know; the
+/// others always answer `false`.
+#[repr(C)]
+pub struct RawValidation {
+    pub ctx: *const c_void,
+    pub is_freed: FnIsFreed,
+}
+
+impl RawValidation {
+    /// Asks `f` whether a range is freed. `f` must outlive the result.
+    pub fn new<F: Fn(*const u8, usize) -> bool>(f: &F) -> RawValidation {
+        RawValidation {
+            ctx: f as *const F as *const c_void,
+            is_freed: raw_is_freed::<F>,
+        }
+    }
+
+    /// Whether `value` lives in freed memory, even partly.
+    pub fn is_freed<T>(&self, value: &T) -> bool {
+        let ptr = value as *const T as *const u8;
+        unsafe { (self.is_freed)(self.ctx, ptr, size_of::<T>()) }
+    }
+}
+
+unsafe extern "C" fn raw_is_freed<F: Fn(*const u8, usize) -> bool>(
+    ctx: *const c_void,
+    ptr: *const u8,
+    size: usize,
+) -> bool {
+    let f = &*(ctx as *const F);
+    f(ptr, size)
+}